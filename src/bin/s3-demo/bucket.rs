@@ -0,0 +1,51 @@
+use aws_sdk_s3::Client;
+use clap::Args;
+use color_eyre::{eyre::{bail, eyre}, Result};
+use rs_aws_sdk_s3_demo::utils::{create_bucket, delete_bucket, S3Path};
+
+/// Create bucket in region of client
+#[derive(Debug, Args)]
+pub struct MbArgs {
+    /// s3://bucket
+    path: S3Path,
+}
+
+/// Delete empty bucket
+#[derive(Debug, Args)]
+pub struct RbArgs {
+    /// s3://bucket
+    path: S3Path,
+
+    /// Delete all objects, versions and incomplete multipart uploads first
+    #[arg(long)]
+    force: bool,
+}
+
+pub async fn mb(client: Client, args: MbArgs) -> Result<()> {
+    let bucket = bucket_name(&args.path)?;
+    let region = client
+        .config()
+        .region()
+        .map(|region| region.to_string())
+        .ok_or_else(|| eyre!("region is not set"))?;
+    create_bucket(client, bucket, &region).await?;
+    println!("make_bucket: {}", bucket);
+
+    Ok(())
+}
+
+pub async fn rb(client: Client, args: RbArgs) -> Result<()> {
+    let bucket = bucket_name(&args.path)?;
+    delete_bucket(client, bucket, args.force).await?;
+    println!("remove_bucket: {}", bucket);
+
+    Ok(())
+}
+
+fn bucket_name(path: &S3Path) -> Result<&str> {
+    if !path.key.is_empty() {
+        bail!("expected s3://bucket without key: {}", path);
+    }
+
+    Ok(&path.bucket)
+}
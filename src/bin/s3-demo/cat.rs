@@ -0,0 +1,44 @@
+use aws_sdk_s3::Client;
+use clap::Args;
+use color_eyre::Result;
+use rs_aws_sdk_s3_demo::utils::{get_aws_object_with_options, Codec, GetOptions, S3Path};
+use tokio::io::{self, AsyncWriteExt, BufReader};
+
+use crate::parse::parse_range;
+
+/// Stream object body to stdout
+#[derive(Debug, Args)]
+pub struct CatArgs {
+    /// s3://bucket/key
+    path: S3Path,
+
+    /// Inclusive byte range like 0-99
+    #[arg(long, value_parser = parse_range)]
+    range: Option<(u64, u64)>,
+
+    /// Decompress gzip body
+    #[arg(long)]
+    gunzip: bool,
+}
+
+pub async fn run(client: Client, args: CatArgs) -> Result<()> {
+    let opts = GetOptions {
+        range: args.range,
+        ..Default::default()
+    };
+    let res = get_aws_object_with_options(client, &args.path.bucket, &args.path.key, &opts).await?;
+
+    let codec = if args.gunzip { Codec::Gzip } else { Codec::None };
+    let mut reader = codec.decoder(BufReader::new(res.body.into_async_read()));
+    let mut stdout = io::stdout();
+    let res = match io::copy(&mut reader, &mut stdout).await {
+        Ok(_) => stdout.flush().await,
+        Err(err) => Err(err),
+    };
+
+    // reader of pipe like head may exit before whole body is written
+    match res {
+        Err(err) if err.kind() != io::ErrorKind::BrokenPipe => Err(err.into()),
+        _ => Ok(()),
+    }
+}
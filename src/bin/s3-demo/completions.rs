@@ -0,0 +1,21 @@
+use std::io;
+
+use clap::{Args, CommandFactory};
+use clap_complete::{generate, Shell};
+use color_eyre::Result;
+
+use crate::Cli;
+
+/// Print shell completion script, e.g. s3-demo completions bash > /etc/bash_completion.d/s3-demo
+#[derive(Debug, Args)]
+pub struct CompletionsArgs {
+    shell: Shell,
+}
+
+pub fn run(args: CompletionsArgs) -> Result<()> {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    generate(args.shell, &mut command, name, &mut io::stdout());
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use rs_aws_sdk_s3_demo::utils::{MultiProgressReporter, ProgressReporter};
+
+/// Progress bars and printed lines of transfer commands
+#[derive(Debug, Clone)]
+pub struct Console {
+    quiet: bool,
+    bars: Option<Arc<MultiProgressReporter>>,
+}
+
+impl Console {
+    /// With quiet only errors are printed, with progress per-file and total bars are drawn on stderr
+    pub fn new(quiet: bool, progress: bool) -> Self {
+        let bars = (progress && !quiet).then(|| Arc::new(MultiProgressReporter::new()));
+        Self { quiet, bars }
+    }
+
+    pub fn reporter(&self) -> Option<Arc<dyn ProgressReporter>> {
+        self.bars
+            .clone()
+            .map(|bars| bars as Arc<dyn ProgressReporter>)
+    }
+
+    /// Print with f unless quiet, bars are hidden while printing
+    pub fn print(&self, f: impl FnOnce()) {
+        if !self.quiet {
+            self.print_error(f);
+        }
+    }
+
+    /// Print error with f, bars are hidden while printing
+    pub fn print_error(&self, f: impl FnOnce()) {
+        match &self.bars {
+            Some(bars) => bars.multi().suspend(f),
+            None => f(),
+        }
+    }
+
+    /// Clear total bar after all transfers finished
+    pub fn finish(&self) {
+        if let Some(bars) = &self.bars {
+            bars.total().finish_and_clear();
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::path::{Path, PathBuf};
+
+use aws_sdk_s3::Client;
+use clap::Args;
+use color_eyre::{eyre::{bail, eyre}, Result};
+use rs_aws_sdk_s3_demo::utils::{copy_object, copy_prefix, download_dir, upload_dir, CopyOptions, S3Path, SymlinkPolicy, TransferManager};
+
+use crate::{console::Console, location::Location};
+
+/// Copy file or directory between local filesystem and AWS S3 or within AWS S3
+#[derive(Debug, Args)]
+pub struct CpArgs {
+    /// Local path or s3://bucket/key
+    src: Location,
+
+    /// Local path or s3://bucket/key, key ending with / or existing directory keeps source file name
+    dst: Location,
+
+    /// Copy all files under directory or prefix
+    #[arg(long, short)]
+    recursive: bool,
+}
+
+pub async fn run(client: Client, args: CpArgs, console: Console) -> Result<()> {
+    let res = if args.recursive {
+        copy_recursive(client, &args, &console).await
+    } else {
+        copy_file(client, &args, &console).await
+    };
+    console.finish();
+
+    res
+}
+
+async fn copy_file(client: Client, args: &CpArgs, console: &Console) -> Result<()> {
+    let mut manager = TransferManager::new(client.clone());
+    if let Some(reporter) = console.reporter() {
+        manager = manager.reporter(reporter);
+    }
+
+    let (kind, dst) = match (&args.src, &args.dst) {
+        (Location::Local(src), Location::S3(dst)) => {
+            let dst = s3_destination(dst, src)?;
+            manager.upload(src, &dst).await?;
+            ("upload", dst.to_string())
+        }
+        (Location::S3(src), Location::Local(dst)) => {
+            let dst = local_destination(dst, &file_name(&src.key)?).await;
+            manager.download(src, &dst).await?;
+            ("download", dst.display().to_string())
+        }
+        (Location::S3(src), Location::S3(dst)) => {
+            let dst = s3_destination(dst, &src.key)?;
+            copy_object(client, src, &dst, &CopyOptions::default()).await?;
+            ("copy", dst.to_string())
+        }
+        (Location::Local(_), Location::Local(_)) => bail!("at least one of source and destination must be s3://bucket/key"),
+    };
+    console.print(|| println!("{}: {} to {}", kind, args.src, dst));
+
+    Ok(())
+}
+
+async fn copy_recursive(client: Client, args: &CpArgs, console: &Console) -> Result<()> {
+    let kind = match (&args.src, &args.dst) {
+        (Location::Local(src), Location::S3(dst)) => {
+            upload_dir(client, src, dst, None, SymlinkPolicy::default(), console.reporter()).await?;
+            "upload"
+        }
+        (Location::S3(src), Location::Local(dst)) => {
+            download_dir(client, src, dst, None, console.reporter()).await?;
+            "download"
+        }
+        (Location::S3(src), Location::S3(dst)) => {
+            copy_prefix(client, src.clone(), dst.clone()).await?;
+            "copy"
+        }
+        (Location::Local(_), Location::Local(_)) => bail!("at least one of source and destination must be s3://bucket/prefix"),
+    };
+    console.print(|| println!("{}: {} to {}", kind, args.src, args.dst));
+
+    Ok(())
+}
+
+/// Last path segment of local path or key
+fn file_name(path: &str) -> Result<String> {
+    path.trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .ok_or_else(|| eyre!("no file name in {}", path))
+}
+
+/// Destination object, file name of src is appended to empty key or key ending with /
+fn s3_destination(dst: &S3Path, src: &str) -> Result<S3Path> {
+    if dst.key.is_empty() || dst.key.ends_with('/') {
+        Ok(dst.join(&file_name(src)?))
+    } else {
+        Ok(dst.clone())
+    }
+}
+
+/// Local file path, file_name is appended to existing directory or path ending with /
+async fn local_destination(dst: &str, file_name: &str) -> PathBuf {
+    let is_dir = dst.ends_with('/') || tokio::fs::metadata(dst).await.is_ok_and(|metadata| metadata.is_dir());
+    if is_dir {
+        Path::new(dst).join(file_name)
+    } else {
+        PathBuf::from(dst)
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+
+use aws_sdk_s3::Client;
+use clap::Args;
+use color_eyre::Result;
+use rs_aws_sdk_s3_demo::utils::{Bucket, PrefixStats, S3Path};
+use serde_json::json;
+
+use crate::{format::format_size, output::{print_json, OutputFormat}};
+
+/// Print objects count and total size under prefix
+#[derive(Debug, Args)]
+pub struct DuArgs {
+    /// s3://bucket/prefix
+    path: S3Path,
+
+    /// Break down by subprefixes up to depth levels below prefix
+    #[arg(long, default_value_t = 0)]
+    depth: usize,
+}
+
+pub async fn run(client: Client, args: DuArgs, output: OutputFormat) -> Result<()> {
+    let bucket = Bucket::new(client, args.path.bucket.clone());
+    let files = bucket.list(&args.path.key).await?;
+
+    let mut total = PrefixStats::default();
+    let mut subprefixes: BTreeMap<String, PrefixStats> = BTreeMap::new();
+    for file in &files {
+        total.add(file);
+        if args.depth > 0 {
+            let relative = file.key.strip_prefix(&args.path.key).unwrap_or(&file.key);
+            let subprefix = subprefix(relative, args.depth);
+            subprefixes.entry(subprefix).or_default().add(file);
+        }
+    }
+
+    for (subprefix, stats) in &subprefixes {
+        let path = S3Path::new(args.path.bucket.clone(), format!("{}{}", args.path.key, subprefix));
+        print_stats(stats, &path, output);
+    }
+    print_stats(&total, &args.path, output);
+
+    Ok(())
+}
+
+/// Leading "directories" of relative key up to depth, empty for objects directly under prefix
+fn subprefix(relative: &str, depth: usize) -> String {
+    let dirs: Vec<&str> = relative.split('/').collect();
+    let dirs = &dirs[..dirs.len() - 1];
+    let dirs = &dirs[..depth.min(dirs.len())];
+    if dirs.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", dirs.join("/"))
+    }
+}
+
+fn print_stats(stats: &PrefixStats, path: &S3Path, output: OutputFormat) {
+    match output {
+        OutputFormat::Text => println!("{:>10}  {:>10} objects  {}", format_size(stats.size), stats.objects, path),
+        OutputFormat::Json => print_json(json!({
+            "path": path.to_string(),
+            "objects": stats.objects,
+            "size": stats.size,
+        })),
+    }
+}
@@ -0,0 +1,122 @@
+use std::{io::{self, BufRead, Write}, time::{Duration, SystemTime}};
+
+use aws_sdk_s3::Client;
+use aws_smithy_types::DateTime;
+use clap::Args;
+use color_eyre::Result;
+use rs_aws_sdk_s3_demo::utils::{delete_keys, list_objects_info, Filters, ListOptions, ObjectInfo, S3Path};
+use serde_json::json;
+
+use crate::{format::format_date, output::{print_json, OutputFormat}, parse::{parse_duration, parse_size}};
+
+/// Find objects under prefix by name, size and age
+#[derive(Debug, Args)]
+pub struct FindArgs {
+    /// s3://bucket/prefix
+    path: S3Path,
+
+    /// Glob pattern of file name (last key segment), * and ? wildcards
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Size like +100M (larger), -1K (smaller) or 10M (exactly), binary units
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_size_filter)]
+    size: Option<SizeFilter>,
+
+    /// Modified within duration like 12h or 7d
+    #[arg(long, value_parser = parse_duration)]
+    newer_than: Option<Duration>,
+
+    /// Modified before duration like 12h or 7d
+    #[arg(long, value_parser = parse_duration)]
+    older_than: Option<Duration>,
+
+    /// Delete found objects after confirmation
+    #[arg(long)]
+    delete: bool,
+
+    /// Delete without confirmation
+    #[arg(long, requires = "delete")]
+    yes: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SizeFilter {
+    Larger(u64),
+    Smaller(u64),
+    Exactly(u64),
+}
+
+impl SizeFilter {
+    fn matches(&self, size: u64) -> bool {
+        match *self {
+            SizeFilter::Larger(val) => size > val,
+            SizeFilter::Smaller(val) => size < val,
+            SizeFilter::Exactly(val) => size == val,
+        }
+    }
+}
+
+fn parse_size_filter(val: &str) -> Result<SizeFilter, String> {
+    if let Some(size) = val.strip_prefix('+') {
+        Ok(SizeFilter::Larger(parse_size(size)?))
+    } else if let Some(size) = val.strip_prefix('-') {
+        Ok(SizeFilter::Smaller(parse_size(size)?))
+    } else {
+        Ok(SizeFilter::Exactly(parse_size(val)?))
+    }
+}
+
+pub async fn run(client: Client, args: FindArgs, output: OutputFormat) -> Result<()> {
+    let S3Path { bucket, key: prefix } = &args.path;
+    let name_filters = args
+        .name
+        .as_ref()
+        .map(|pattern| Filters::new().exclude("*").include(pattern));
+    let now = SystemTime::now();
+    let newer_than = args.newer_than.map(|age| DateTime::from(now - age));
+    let older_than = args.older_than.map(|age| DateTime::from(now - age));
+
+    let files: Vec<ObjectInfo> = list_objects_info(client.clone(), bucket, prefix, &ListOptions::default())
+        .await?
+        .into_iter()
+        .filter(|file| name_filters.as_ref().is_none_or(|filters| filters.is_included(file_name(&file.key))))
+        .filter(|file| args.size.is_none_or(|size| size.matches(file.size as u64)))
+        .filter(|file| newer_than.is_none_or(|date| file.last_modified.is_some_and(|val| val > date)))
+        .filter(|file| older_than.is_none_or(|date| file.last_modified.is_some_and(|val| val < date)))
+        .collect();
+
+    for file in &files {
+        let path = S3Path::new(bucket.clone(), file.key.clone());
+        match output {
+            OutputFormat::Text => println!("{}", path),
+            OutputFormat::Json => print_json(json!({
+                "path": path.to_string(),
+                "size": file.size,
+                "last_modified": format_date(file.last_modified.as_ref()),
+            })),
+        }
+    }
+
+    if args.delete && !files.is_empty() && (args.yes || confirm(&format!("Delete {} objects?", files.len()))?) {
+        let keys: Vec<String> = files.into_iter().map(|file| file.key).collect();
+        delete_keys(client, bucket, &keys).await?;
+        eprintln!("deleted {} objects", keys.len());
+    }
+
+    Ok(())
+}
+
+fn file_name(key: &str) -> &str {
+    key.rsplit('/').next().unwrap_or(key)
+}
+
+/// Ask yes/no question on stderr and read answer from stdin, no by default
+fn confirm(question: &str) -> Result<bool> {
+    eprint!("{} [y/N] ", question);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
@@ -0,0 +1,24 @@
+use aws_smithy_types::{date_time::Format, DateTime};
+
+const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+
+/// Format size in bytes with binary units, e.g. 1.5 GiB
+pub fn format_size(bytes: i64) -> String {
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size.abs() >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{} {}", bytes, UNITS[0]),
+        _ => format!("{:.1} {}", size, UNITS[unit]),
+    }
+}
+
+/// Format time as RFC 3339, empty if unknown
+pub fn format_date(date: Option<&DateTime>) -> String {
+    date.and_then(|date| date.fmt(Format::DateTime).ok())
+        .unwrap_or_default()
+}
@@ -0,0 +1,47 @@
+use aws_sdk_s3::Client;
+use clap::Args;
+use color_eyre::{eyre::eyre, Result};
+use rs_aws_sdk_s3_demo::utils::{get_object_metadata, S3Path};
+use serde_json::json;
+
+use crate::{format::format_date, output::{print_json, OutputFormat}};
+
+/// Print object metadata
+#[derive(Debug, Args)]
+pub struct HeadArgs {
+    /// s3://bucket/key
+    path: S3Path,
+}
+
+pub async fn run(client: Client, args: HeadArgs, output: OutputFormat) -> Result<()> {
+    let metadata = get_object_metadata(client, &args.path.bucket, &args.path.key)
+        .await?
+        .ok_or_else(|| eyre!("not found: {}", args.path))?;
+
+    let last_modified = format_date(metadata.last_modified.as_ref());
+    match output {
+        OutputFormat::Text => {
+            println!("size: {}", metadata.size);
+            println!("last_modified: {}", last_modified);
+            println!("e_tag: {}", metadata.e_tag.unwrap_or_default());
+            println!("content_type: {}", metadata.content_type.unwrap_or_default());
+            println!("storage_class: {}", metadata.storage_class.unwrap_or_default());
+            let mut user_metadata: Vec<_> = metadata.metadata.into_iter().collect();
+            user_metadata.sort();
+            for (name, value) in user_metadata {
+                println!("x-amz-meta-{}: {}", name, value);
+            }
+        }
+        OutputFormat::Json => print_json(json!({
+            "path": args.path.to_string(),
+            "size": metadata.size,
+            "last_modified": last_modified,
+            "e_tag": metadata.e_tag,
+            "content_type": metadata.content_type,
+            "storage_class": metadata.storage_class,
+            "metadata": metadata.metadata,
+        })),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use std::fmt;
+
+use rs_aws_sdk_s3_demo::utils::S3Path;
+
+/// Local path or s3://bucket/key argument
+#[derive(Debug, Clone)]
+pub enum Location {
+    Local(String),
+    S3(S3Path),
+}
+
+impl Location {
+    /// Path of file relative to this directory or prefix
+    pub fn join(&self, relative: &str) -> String {
+        match self {
+            Location::Local(dir) => format!("{}/{}", dir.trim_end_matches('/'), relative),
+            Location::S3(s3path) => s3path.join(relative).to_string(),
+        }
+    }
+}
+
+impl From<&str> for Location {
+    fn from(val: &str) -> Self {
+        match val.parse() {
+            Ok(s3path) if val.starts_with("s3://") => Location::S3(s3path),
+            _ => Location::Local(val.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Local(dir) => write!(f, "{}", dir),
+            Location::S3(s3path) => write!(f, "{}", s3path),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use aws_sdk_s3::Client;
+use clap::Args;
+use color_eyre::Result;
+use rs_aws_sdk_s3_demo::utils::{list_objects_info, ListOptions, S3Path};
+use serde_json::json;
+
+use crate::{format::{format_date, format_size}, output::{print_json, OutputFormat}};
+
+/// List objects under prefix
+#[derive(Debug, Args)]
+pub struct LsArgs {
+    /// s3://bucket/prefix
+    path: S3Path,
+
+    /// Print sizes with binary units
+    #[arg(long)]
+    human_readable: bool,
+}
+
+pub async fn run(client: Client, args: LsArgs, output: OutputFormat) -> Result<()> {
+    let files = list_objects_info(client, &args.path.bucket, &args.path.key, &ListOptions::default()).await?;
+    for file in files {
+        match output {
+            OutputFormat::Text => {
+                let size = if args.human_readable { format_size(file.size) } else { file.size.to_string() };
+                println!("{:<20}  {:>10}  {}", format_date(file.last_modified.as_ref()), size, file.key);
+            }
+            OutputFormat::Json => print_json(json!({
+                "key": file.key,
+                "size": file.size,
+                "last_modified": format_date(file.last_modified.as_ref()),
+                "e_tag": file.e_tag,
+                "storage_class": file.storage_class,
+            })),
+        }
+    }
+
+    Ok(())
+}
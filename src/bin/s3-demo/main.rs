@@ -0,0 +1,108 @@
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use color_eyre::Result;
+use rs_aws_sdk_s3_demo::utils::{get_aws_client_with_options, ClientOptions};
+
+use crate::{console::Console, output::OutputFormat};
+
+mod bucket;
+mod cat;
+mod completions;
+mod console;
+mod cp;
+mod du;
+mod find;
+mod format;
+mod head;
+mod location;
+mod ls;
+mod output;
+mod parse;
+mod pipe;
+mod presign;
+mod sync;
+
+/// AWS S3 command line tool
+#[derive(Debug, Parser)]
+#[command(name = "s3-demo", version, about)]
+struct Cli {
+    /// AWS region, AWS_REGION or region of profile by default
+    #[arg(long, global = true)]
+    region: Option<String>,
+
+    /// Profile of AWS config and credentials files, AWS_PROFILE or default profile by default
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Endpoint of S3-compatible storage like MinIO, buckets are addressed path-style
+    #[arg(long, global = true)]
+    endpoint_url: Option<String>,
+
+    /// Output format of ls, du, find, head and sync
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// Print only errors
+    #[arg(long, short, global = true)]
+    quiet: bool,
+
+    /// Don't draw progress bars of cp and sync
+    #[arg(long, global = true)]
+    no_progress: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    Cat(cat::CatArgs),
+    Completions(completions::CompletionsArgs),
+    Cp(cp::CpArgs),
+    Du(du::DuArgs),
+    Find(find::FindArgs),
+    Head(head::HeadArgs),
+    Ls(ls::LsArgs),
+    Mb(bucket::MbArgs),
+    Pipe(pipe::PipeArgs),
+    Presign(presign::PresignArgs),
+    Rb(bucket::RbArgs),
+    Sync(sync::SyncArgs),
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    color_eyre::install()?;
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches)?;
+    let client_options = ClientOptions {
+        region: cli.region,
+        profile: cli.profile,
+        force_path_style: cli.endpoint_url.is_some(),
+        endpoint_url: cli.endpoint_url,
+    };
+    let client = get_aws_client_with_options(&client_options).await;
+    let console = Console::new(cli.quiet, !cli.no_progress);
+
+    match cli.command {
+        Command::Cat(args) => cat::run(client, args).await,
+        Command::Completions(args) => completions::run(args),
+        Command::Cp(args) => cp::run(client, args, console).await,
+        Command::Du(args) => du::run(client, args, cli.output).await,
+        Command::Find(args) => find::run(client, args, cli.output).await,
+        Command::Head(args) => head::run(client, args, cli.output).await,
+        Command::Ls(args) => ls::run(client, args, cli.output).await,
+        Command::Mb(args) => bucket::mb(client, args).await,
+        Command::Pipe(args) => pipe::run(client, args, console).await,
+        Command::Presign(args) => presign::run(client, args).await,
+        Command::Rb(args) => bucket::rb(client, args).await,
+        Command::Sync(args) => sync::run(client, args, subcommand_matches(&matches), cli.output, console).await,
+    }
+}
+
+/// Matches of the subcommand being run
+fn subcommand_matches(matches: &ArgMatches) -> &ArgMatches {
+    matches
+        .subcommand()
+        .map(|(_, matches)| matches)
+        .unwrap_or(matches)
+}
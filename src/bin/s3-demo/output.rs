@@ -0,0 +1,17 @@
+use clap::ValueEnum;
+use serde_json::Value;
+
+/// Output of commands
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable text
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// Print JSON object as one line
+pub fn print_json(value: Value) {
+    println!("{}", value);
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+/// Parse duration like 90s, 15m, 12h or 7d, plain number is seconds
+pub fn parse_duration(val: &str) -> Result<Duration, String> {
+    let (number, unit) = split_unit(val);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration: {}", val))?;
+    let secs = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(format!("invalid duration unit: {}", unit)),
+    };
+
+    Ok(Duration::from_secs(number * secs))
+}
+
+/// Split value into leading number and unit suffix
+fn split_unit(val: &str) -> (&str, &str) {
+    let idx = val
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(val.len());
+
+    val.split_at(idx)
+}
+
+/// Parse inclusive byte range like 0-99 or bytes=0-99 into (first, last)
+pub fn parse_range(val: &str) -> Result<(u64, u64), String> {
+    let range = val.strip_prefix("bytes=").unwrap_or(val);
+    let (first, last) = range
+        .split_once('-')
+        .ok_or_else(|| format!("invalid range: {}", val))?;
+    let first: u64 = first
+        .parse()
+        .map_err(|_| format!("invalid range start: {}", val))?;
+    let last: u64 = last
+        .parse()
+        .map_err(|_| format!("invalid range end: {}", val))?;
+    if last < first {
+        return Err(format!("range end before start: {}", val));
+    }
+
+    Ok((first, last))
+}
+
+/// Parse size like 512, 10K, 100M or 2G with binary units
+pub fn parse_size(val: &str) -> Result<u64, String> {
+    let (number, unit) = split_unit(val);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid size: {}", val))?;
+    let multiplier: u64 = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return Err(format!("invalid size unit: {}", unit)),
+    };
+
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("size too large: {}", val))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(15 * 60));
+        assert_eq!(parse_duration("12h").unwrap(), Duration::from_secs(12 * 60 * 60));
+        assert_eq!(parse_duration("7d").unwrap(), Duration::from_secs(7 * 24 * 60 * 60));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("5w").is_err());
+        assert!(parse_duration("-5s").is_err());
+    }
+
+    #[test]
+    fn parse_range_bounds() {
+        assert_eq!(parse_range("0-99").unwrap(), (0, 99));
+        assert_eq!(parse_range("bytes=100-100").unwrap(), (100, 100));
+        assert!(parse_range("99-0").is_err());
+        assert!(parse_range("100").is_err());
+        assert!(parse_range("100-").is_err());
+        assert!(parse_range("a-b").is_err());
+    }
+
+    #[test]
+    fn parse_size_units() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("512B").unwrap(), 512);
+        assert_eq!(parse_size("10K").unwrap(), 10 * 1024);
+        assert_eq!(parse_size("100m").unwrap(), 100 * 1024 * 1024);
+        assert_eq!(parse_size("2G").unwrap(), 2 * 1024 * 1024 * 1024);
+        assert_eq!(parse_size("1T").unwrap(), 1 << 40);
+        assert!(parse_size("1P").is_err());
+        assert!(parse_size("K").is_err());
+        assert!(parse_size("99999999999T").is_err());
+    }
+}
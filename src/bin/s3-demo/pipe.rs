@@ -0,0 +1,45 @@
+use aws_sdk_s3::Client;
+use clap::Args;
+use color_eyre::Result;
+use rs_aws_sdk_s3_demo::utils::{S3Path, S3Writer, READ_BUF_SIZE};
+use tokio::io::{self, AsyncReadExt};
+
+use crate::{console::Console, format::format_size};
+
+/// Upload stdin to object by streaming multipart upload, e.g. pg_dump db | s3-demo pipe s3://bucket/db.sql
+#[derive(Debug, Args)]
+pub struct PipeArgs {
+    /// s3://bucket/key
+    path: S3Path,
+
+    #[arg(long)]
+    content_type: Option<String>,
+
+    /// Storage class like STANDARD_IA or GLACIER
+    #[arg(long)]
+    storage_class: Option<String>,
+}
+
+pub async fn run(client: Client, args: PipeArgs, console: Console) -> Result<()> {
+    let mut writer = S3Writer::new(client, args.path.bucket.clone(), args.path.key.clone());
+    if let Some(content_type) = args.content_type {
+        writer = writer.content_type(content_type);
+    }
+    if let Some(storage_class) = args.storage_class {
+        writer = writer.storage_class(storage_class);
+    }
+
+    let mut stdin = io::stdin();
+    let mut buf = vec![0; READ_BUF_SIZE];
+    loop {
+        let len = stdin.read(&mut buf).await?;
+        if len == 0 {
+            break;
+        }
+        writer.write(&buf[..len]).await?;
+    }
+    let report = writer.finish().await?;
+    console.print(|| eprintln!("upload: {} to {} ({} parts)", format_size(report.bytes as i64), args.path, report.parts));
+
+    Ok(())
+}
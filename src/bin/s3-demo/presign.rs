@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use aws_sdk_s3::Client;
+use clap::{Args, ValueEnum};
+use color_eyre::Result;
+use rs_aws_sdk_s3_demo::utils::{presign_get, presign_put, S3Path, SignedHeaders};
+
+use crate::parse::parse_duration;
+
+/// Print presigned url of object
+#[derive(Debug, Args)]
+pub struct PresignArgs {
+    method: PresignMethod,
+
+    /// s3://bucket/key
+    path: S3Path,
+
+    /// Url lifetime like 90s, 15m, 12h or 7d, at most 7d
+    #[arg(long, default_value = "15m", value_parser = parse_duration)]
+    expires: Duration,
+
+    /// Content type the uploader must send, put only
+    #[arg(long)]
+    content_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum PresignMethod {
+    Get,
+    Put,
+}
+
+pub async fn run(client: Client, args: PresignArgs) -> Result<()> {
+    let S3Path { bucket, key } = &args.path;
+    match args.method {
+        PresignMethod::Get => {
+            let url = presign_get(client, bucket, key, args.expires, None).await?;
+            println!("{}", url);
+        }
+        PresignMethod::Put => {
+            let signed_headers = SignedHeaders {
+                content_type: args.content_type,
+                ..Default::default()
+            };
+            let presigned = presign_put(client, bucket, key, args.expires, &signed_headers).await?;
+            println!("{}", presigned.url);
+            // headers go to stderr to keep stdout a single url
+            for (name, value) in &presigned.headers {
+                eprintln!("{}: {}", name, value);
+            }
+        }
+    }
+
+    Ok(())
+}
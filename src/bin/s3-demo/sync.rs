@@ -0,0 +1,165 @@
+use aws_sdk_s3::Client;
+use clap::{ArgMatches, Args};
+use color_eyre::{eyre::bail, Result};
+use rs_aws_sdk_s3_demo::utils::{
+    plan_sync_down, plan_sync_s3, plan_sync_up, sync_down, sync_s3, sync_up, CompareStrategy, FilterRule, Filters,
+    SyncAction, SyncActionKind, SyncEvent, SyncOptions, SyncPlan, SyncReport,
+};
+use serde_json::json;
+use tokio::sync::mpsc;
+
+use crate::{console::Console, location::Location, output::{print_json, OutputFormat}};
+
+/// Sync local directory and AWS S3 prefix or two AWS S3 prefixes
+#[derive(Debug, Args)]
+pub struct SyncArgs {
+    /// Local directory or s3://bucket/prefix
+    src: Location,
+
+    /// Local directory or s3://bucket/prefix
+    dst: Location,
+
+    /// Delete destination files missing in source
+    #[arg(long)]
+    delete: bool,
+
+    /// Print what would be done without doing it
+    #[arg(long)]
+    dryrun: bool,
+
+    /// Exclude paths matching glob pattern, later --exclude and --include take precedence
+    #[arg(long, value_name = "PATTERN")]
+    exclude: Vec<String>,
+
+    /// Include paths matching glob pattern, later --exclude and --include take precedence
+    #[arg(long, value_name = "PATTERN")]
+    include: Vec<String>,
+
+    /// Compare files by ETag instead of modification time
+    #[arg(long)]
+    checksum: bool,
+}
+
+/// Run sync, matches of sync subcommand give order of --exclude and --include
+pub async fn run(client: Client, args: SyncArgs, matches: &ArgMatches, output: OutputFormat, console: Console) -> Result<()> {
+    let (events, mut receiver) = mpsc::unbounded_channel();
+    let mut opts = SyncOptions {
+        delete_extraneous: args.delete,
+        dry_run: args.dryrun,
+        filters: filters(&args, matches),
+        progress: Some(events),
+        reporter: console.reporter(),
+        ..Default::default()
+    };
+    if args.checksum {
+        opts.compare = CompareStrategy::Checksum;
+    }
+
+    if args.dryrun {
+        let plan = plan(client, &args, &opts).await?;
+        for action in plan.actions.iter().filter(|action| action.kind != SyncActionKind::Skip) {
+            console.print(|| print_action(action, &args.src, &args.dst, output, true));
+        }
+        return Ok(());
+    }
+
+    let printer = {
+        let (src, dst, console) = (args.src.clone(), args.dst.clone(), console.clone());
+        tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                match event {
+                    SyncEvent::Started(_) => {}
+                    SyncEvent::Finished(action) => console.print(|| print_action(&action, &src, &dst, output, false)),
+                    SyncEvent::Failed(action, err) => console.print_error(|| match output {
+                        OutputFormat::Text => eprintln!("failed {}: {}", describe(&action, &src, &dst), err),
+                        OutputFormat::Json => print_json(json!({
+                            "action": action.kind.to_string().to_lowercase(),
+                            "path": action.relative,
+                            "error": err,
+                        })),
+                    }),
+                }
+            }
+        })
+    };
+    let report = sync(client, &args, &opts).await;
+    // dropping options closes event channel so printer finishes
+    drop(opts);
+    printer.await?;
+    console.finish();
+
+    let report = report?;
+    if !report.failed.is_empty() {
+        bail!("{} files failed", report.failed.len());
+    }
+
+    Ok(())
+}
+
+async fn plan(client: Client, args: &SyncArgs, opts: &SyncOptions) -> Result<SyncPlan> {
+    let plan = match (&args.src, &args.dst) {
+        (Location::Local(src), Location::S3(dst)) => plan_sync_up(client, src, dst, opts).await?,
+        (Location::S3(src), Location::Local(dst)) => plan_sync_down(client, src, dst, opts).await?,
+        (Location::S3(src), Location::S3(dst)) => plan_sync_s3(client, src, dst, opts).await?,
+        (Location::Local(_), Location::Local(_)) => bail!("at least one of source and destination must be s3://bucket/prefix"),
+    };
+
+    Ok(plan)
+}
+
+async fn sync(client: Client, args: &SyncArgs, opts: &SyncOptions) -> Result<SyncReport> {
+    let report = match (&args.src, &args.dst) {
+        (Location::Local(src), Location::S3(dst)) => sync_up(client, src, dst, opts).await?,
+        (Location::S3(src), Location::Local(dst)) => sync_down(client, src, dst, opts).await?,
+        (Location::S3(src), Location::S3(dst)) => sync_s3(client, src, dst, opts).await?,
+        (Location::Local(_), Location::Local(_)) => bail!("at least one of source and destination must be s3://bucket/prefix"),
+    };
+
+    Ok(report)
+}
+
+/// Filters in command line order, clap keeps values of each flag separately
+fn filters(args: &SyncArgs, matches: &ArgMatches) -> Filters {
+    let mut rules = Vec::new();
+    if let Some(indices) = matches.indices_of("exclude") {
+        rules.extend(indices.zip(&args.exclude).map(|(idx, pattern)| (idx, FilterRule::Exclude(pattern.clone()))));
+    }
+    if let Some(indices) = matches.indices_of("include") {
+        rules.extend(indices.zip(&args.include).map(|(idx, pattern)| (idx, FilterRule::Include(pattern.clone()))));
+    }
+    rules.sort_by_key(|(idx, _)| *idx);
+
+    Filters {
+        rules: rules.into_iter().map(|(_, rule)| rule).collect(),
+    }
+}
+
+fn print_action(action: &SyncAction, src: &Location, dst: &Location, output: OutputFormat, dry_run: bool) {
+    match output {
+        OutputFormat::Text if dry_run => println!("(dryrun) {}", describe(action, src, dst)),
+        OutputFormat::Text => println!("{}", describe(action, src, dst)),
+        OutputFormat::Json => {
+            let source = match action.kind {
+                SyncActionKind::Delete | SyncActionKind::DeleteLocal => None,
+                _ => Some(src.join(&action.relative)),
+            };
+            print_json(json!({
+                "action": action.kind.to_string().to_lowercase(),
+                "path": action.relative,
+                "source": source,
+                "destination": dst.join(&action.relative),
+                "size": action.size,
+                "dryrun": dry_run,
+            }));
+        }
+    }
+}
+
+/// Describe action like AWS CLI, e.g. upload: dir/a.txt to s3://bucket/prefix/a.txt
+fn describe(action: &SyncAction, src: &Location, dst: &Location) -> String {
+    let kind = action.kind.to_string().to_lowercase();
+    match action.kind {
+        SyncActionKind::Delete | SyncActionKind::DeleteLocal => format!("{}: {}", kind, dst.join(&action.relative)),
+        _ => format!("{}: {} to {}", kind, src.join(&action.relative), dst.join(&action.relative)),
+    }
+}
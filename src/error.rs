@@ -1,15 +1,76 @@
-use std::io::Error as IoError;
+use std::fmt;
+use std::io::{Error as IoError, ErrorKind};
+use std::time::Duration;
 
 use aws_sdk_s3::operation::get_object::GetObjectError;
-use aws_sdk_s3::error::SdkError;
+use aws_sdk_s3::operation::head_object::HeadObjectError;
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::operation::{RequestId, RequestIdExt};
 use aws_sdk_s3::operation::put_object::PutObjectError;
 use aws_sdk_s3::operation::list_objects_v2::ListObjectsV2Error;
 use aws_sdk_s3::operation::create_multipart_upload::CreateMultipartUploadError;
 use aws_sdk_s3::operation::upload_part::UploadPartError;
+use aws_sdk_s3::operation::upload_part_copy::UploadPartCopyError;
 use aws_sdk_s3::operation::complete_multipart_upload::CompleteMultipartUploadError;
+use aws_sdk_s3::operation::copy_object::CopyObjectError;
+use aws_sdk_s3::operation::delete_object::DeleteObjectError;
+use aws_sdk_s3::operation::delete_objects::DeleteObjectsError;
+use aws_sdk_s3::operation::get_object_tagging::GetObjectTaggingError;
+use aws_sdk_s3::operation::put_object_tagging::PutObjectTaggingError;
+use aws_sdk_s3::operation::delete_object_tagging::DeleteObjectTaggingError;
+use aws_sdk_s3::operation::put_object_legal_hold::PutObjectLegalHoldError;
+use aws_sdk_s3::operation::get_object_legal_hold::GetObjectLegalHoldError;
+use aws_sdk_s3::operation::get_object_attributes::GetObjectAttributesError;
+use aws_sdk_s3::operation::get_object_retention::GetObjectRetentionError;
+use aws_sdk_s3::operation::put_object_retention::PutObjectRetentionError;
+use aws_sdk_s3::operation::create_bucket::CreateBucketError;
+use aws_sdk_s3::operation::delete_bucket::DeleteBucketError;
+use aws_sdk_s3::operation::list_object_versions::ListObjectVersionsError;
+use aws_sdk_s3::operation::list_multipart_uploads::ListMultipartUploadsError;
+use aws_sdk_s3::operation::abort_multipart_upload::AbortMultipartUploadError;
+use aws_sdk_s3::operation::head_bucket::HeadBucketError;
+use aws_sdk_s3::operation::list_buckets::ListBucketsError;
+use aws_sdk_s3::operation::put_bucket_lifecycle_configuration::PutBucketLifecycleConfigurationError;
+use aws_sdk_s3::operation::get_bucket_lifecycle_configuration::GetBucketLifecycleConfigurationError;
+use aws_sdk_s3::operation::get_bucket_policy::GetBucketPolicyError;
+use aws_sdk_s3::operation::put_bucket_policy::PutBucketPolicyError;
+use aws_sdk_s3::operation::get_bucket_cors::GetBucketCorsError;
+use aws_sdk_s3::operation::put_bucket_cors::PutBucketCorsError;
+use aws_sdk_s3::operation::delete_bucket_cors::DeleteBucketCorsError;
+use aws_sdk_s3::operation::get_bucket_location::GetBucketLocationError;
+use aws_sdk_s3::operation::put_public_access_block::PutPublicAccessBlockError;
+use aws_sdk_s3::operation::get_public_access_block::GetPublicAccessBlockError;
+use aws_sdk_s3::operation::put_bucket_inventory_configuration::PutBucketInventoryConfigurationError;
+use aws_sdk_s3::operation::select_object_content::SelectObjectContentError;
+use aws_sdk_s3::types::error::SelectObjectContentEventStreamError;
+#[cfg(feature = "events")]
+use aws_sdk_sqs::operation::receive_message::ReceiveMessageError;
+#[cfg(feature = "events")]
+use aws_sdk_sqs::operation::delete_message::DeleteMessageError;
+use aws_sdk_s3::operation::restore_object::RestoreObjectError;
+use aws_sdk_s3::error::BuildError;
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::presigning::PresigningConfigError;
+#[cfg(feature = "zip")]
+use async_zip::error::ZipError;
 use aws_smithy_types::byte_stream::error::Error as AWSSmithyError;
+use aws_smithy_types::event_stream::RawMessage;
 use color_eyre::eyre::Report;
+#[cfg(feature = "datafusion")]
+use datafusion::error::DataFusionError;
+#[cfg(any(feature = "csv", feature = "inventory"))]
+use csv_async::Error as CsvError;
+#[cfg(feature = "parquet")]
+use parquet::errors::ParquetError;
+use reqwest::Error as ReqwestError;
+use serde_json::Error as SerdeJsonError;
 use thiserror::Error;
+use tokio::sync::AcquireError;
+use tokio::task::JoinError;
+#[cfg(feature = "stream")]
+use tokio_util::codec::LinesCodecError;
+
+use crate::utils::{RETRYABLE_CODES, THROTTLING_CODES};
 
 #[derive(Debug, Error)]
 pub enum UtilsError {
@@ -17,26 +78,562 @@ pub enum UtilsError {
     IoError(#[from] IoError),
 
     #[error("AWS GetObjectError error")]
-    GetObjectError(#[from] SdkError<GetObjectError>),
+    GetObjectError(#[source] Box<SdkError<GetObjectError>>),
+
+    #[error("AWS HeadObjectError error")]
+    HeadObjectError(#[source] Box<SdkError<HeadObjectError>>),
 
     #[error("AWS ListObjectsV2Error error")]
-    ListObjectsV2Error(#[from] SdkError<ListObjectsV2Error>),
+    ListObjectsV2Error(#[source] Box<SdkError<ListObjectsV2Error>>),
 
     #[error("AWS CreateMultipartUploadError error")]
-    CreateMultipartUploadError(#[from] SdkError<CreateMultipartUploadError>),
+    CreateMultipartUploadError(#[source] Box<SdkError<CreateMultipartUploadError>>),
 
     #[error("AWS CompleteMultipartUploadError error")]
-    CompleteMultipartUploadError(#[from] SdkError<CompleteMultipartUploadError>),
+    CompleteMultipartUploadError(#[source] Box<SdkError<CompleteMultipartUploadError>>),
 
     #[error("AWS PutObjectError error")]
-    PutObjectError(#[from] SdkError<PutObjectError>),
+    PutObjectError(#[source] Box<SdkError<PutObjectError>>),
 
     #[error("AWS UploadPartError error")]
-    UploadPartError(#[from] SdkError<UploadPartError>),
+    UploadPartError(#[source] Box<SdkError<UploadPartError>>),
+
+    #[error("AWS UploadPartCopyError error")]
+    UploadPartCopyError(#[source] Box<SdkError<UploadPartCopyError>>),
+
+    #[error("AWS CopyObjectError error")]
+    CopyObjectError(#[source] Box<SdkError<CopyObjectError>>),
+
+    #[error("AWS DeleteObjectError error")]
+    DeleteObjectError(#[source] Box<SdkError<DeleteObjectError>>),
+
+    #[error("AWS DeleteObjectsError error")]
+    DeleteObjectsError(#[source] Box<SdkError<DeleteObjectsError>>),
+
+    #[error("AWS GetObjectTaggingError error")]
+    GetObjectTaggingError(#[source] Box<SdkError<GetObjectTaggingError>>),
+
+    #[error("AWS PutObjectTaggingError error")]
+    PutObjectTaggingError(#[source] Box<SdkError<PutObjectTaggingError>>),
+
+    #[error("AWS DeleteObjectTaggingError error")]
+    DeleteObjectTaggingError(#[source] Box<SdkError<DeleteObjectTaggingError>>),
+
+    #[error("AWS PutObjectLegalHoldError error")]
+    PutObjectLegalHoldError(#[source] Box<SdkError<PutObjectLegalHoldError>>),
+
+    #[error("AWS GetObjectLegalHoldError error")]
+    GetObjectLegalHoldError(#[source] Box<SdkError<GetObjectLegalHoldError>>),
+
+    #[error("AWS GetObjectAttributesError error")]
+    GetObjectAttributesError(#[source] Box<SdkError<GetObjectAttributesError>>),
+
+    #[error("AWS GetObjectRetentionError error")]
+    GetObjectRetentionError(#[source] Box<SdkError<GetObjectRetentionError>>),
+
+    #[error("AWS PutObjectRetentionError error")]
+    PutObjectRetentionError(#[source] Box<SdkError<PutObjectRetentionError>>),
+
+    #[error("AWS CreateBucketError error")]
+    CreateBucketError(#[source] Box<SdkError<CreateBucketError>>),
+
+    #[error("AWS DeleteBucketError error")]
+    DeleteBucketError(#[source] Box<SdkError<DeleteBucketError>>),
+
+    #[error("AWS ListObjectVersionsError error")]
+    ListObjectVersionsError(#[source] Box<SdkError<ListObjectVersionsError>>),
+
+    #[error("AWS ListMultipartUploadsError error")]
+    ListMultipartUploadsError(#[source] Box<SdkError<ListMultipartUploadsError>>),
+
+    #[error("AWS AbortMultipartUploadError error")]
+    AbortMultipartUploadError(#[source] Box<SdkError<AbortMultipartUploadError>>),
+
+    #[error("AWS HeadBucketError error")]
+    HeadBucketError(#[source] Box<SdkError<HeadBucketError>>),
+
+    #[error("AWS ListBucketsError error")]
+    ListBucketsError(#[source] Box<SdkError<ListBucketsError>>),
+
+    #[error("AWS PutBucketLifecycleConfigurationError error")]
+    PutBucketLifecycleConfigurationError(#[source] Box<SdkError<PutBucketLifecycleConfigurationError>>),
+
+    #[error("AWS GetBucketLifecycleConfigurationError error")]
+    GetBucketLifecycleConfigurationError(#[source] Box<SdkError<GetBucketLifecycleConfigurationError>>),
+
+    #[error("AWS GetBucketPolicyError error")]
+    GetBucketPolicyError(#[source] Box<SdkError<GetBucketPolicyError>>),
+
+    #[error("AWS PutBucketPolicyError error")]
+    PutBucketPolicyError(#[source] Box<SdkError<PutBucketPolicyError>>),
+
+    #[error("AWS GetBucketCorsError error")]
+    GetBucketCorsError(#[source] Box<SdkError<GetBucketCorsError>>),
+
+    #[error("AWS PutBucketCorsError error")]
+    PutBucketCorsError(#[source] Box<SdkError<PutBucketCorsError>>),
+
+    #[error("AWS DeleteBucketCorsError error")]
+    DeleteBucketCorsError(#[source] Box<SdkError<DeleteBucketCorsError>>),
+
+    #[error("AWS GetBucketLocationError error")]
+    GetBucketLocationError(#[source] Box<SdkError<GetBucketLocationError>>),
+
+    #[error("AWS PutPublicAccessBlockError error")]
+    PutPublicAccessBlockError(#[source] Box<SdkError<PutPublicAccessBlockError>>),
+
+    #[error("AWS GetPublicAccessBlockError error")]
+    GetPublicAccessBlockError(#[source] Box<SdkError<GetPublicAccessBlockError>>),
+
+    #[error("AWS PutBucketInventoryConfigurationError error")]
+    PutBucketInventoryConfigurationError(#[source] Box<SdkError<PutBucketInventoryConfigurationError>>),
+
+    #[error("AWS SelectObjectContentError error")]
+    SelectObjectContentError(#[source] Box<SdkError<SelectObjectContentError>>),
+
+    #[error("AWS SelectObjectContentEventStreamError error")]
+    SelectObjectContentEventStreamError(#[source] Box<SdkError<SelectObjectContentEventStreamError, RawMessage>>),
+
+    #[cfg(feature = "events")]
+    #[error("AWS SQS ReceiveMessageError error")]
+    ReceiveMessageError(#[source] Box<SdkError<ReceiveMessageError>>),
+
+    #[cfg(feature = "events")]
+    #[error("AWS SQS DeleteMessageError error")]
+    DeleteMessageError(#[source] Box<SdkError<DeleteMessageError>>),
+
+    #[error("AWS RestoreObjectError error")]
+    RestoreObjectError(#[source] Box<SdkError<RestoreObjectError>>),
+
+    #[error("AWS BuildError error")]
+    BuildError(#[from] BuildError),
+
+    #[error("AWS PresigningConfigError error")]
+    PresigningConfigError(#[from] PresigningConfigError),
 
     #[error("AWSSmithy error")]
     AWSSmithyError(#[from] AWSSmithyError),
     
+    #[error("Reqwest error")]
+    ReqwestError(#[from] ReqwestError),
+
+    #[error("Serde JSON error")]
+    SerdeJsonError(#[from] SerdeJsonError),
+
+    #[cfg(feature = "stream")]
+    #[error("Lines codec error")]
+    LinesCodecError(#[from] LinesCodecError),
+
+    #[cfg(any(feature = "csv", feature = "inventory"))]
+    #[error("CSV error")]
+    CsvError(#[from] CsvError),
+
+    #[cfg(feature = "parquet")]
+    #[error("Parquet error")]
+    ParquetError(#[from] ParquetError),
+
+    #[cfg(feature = "zip")]
+    #[error("Zip error")]
+    ZipError(#[from] ZipError),
+
+    #[cfg(feature = "datafusion")]
+    #[error("DataFusion error")]
+    DataFusionError(#[from] DataFusionError),
+
+    #[error("Tokio join error")]
+    JoinError(#[from] JoinError),
+
+    #[error("Tokio semaphore closed")]
+    AcquireError(#[from] AcquireError),
+
+    #[error("Operation cancelled")]
+    Cancelled,
+
+    #[error("Operation timed out after {elapsed:?} with {bytes} bytes transferred")]
+    Timeout { elapsed: Duration, bytes: u64 },
+
+    #[error("Bucket not found: {0}")]
+    BucketNotFound(String),
+
+    #[error("Object not found: {0}")]
+    ObjectNotFound(String),
+
+    #[error("Bucket {bucket} exists but access is denied, check credentials and bucket policy")]
+    AccessDenied { bucket: String },
+
+    #[error("Invalid S3 key: {0}")]
+    InvalidKey(String),
+
+    #[error("Missing upload id of multipart upload: {0}")]
+    MissingUploadId(String),
+
+    #[error("Missing ETag of: {0}")]
+    MissingETag(String),
+
+    #[error("Too many chunks: {count} exceeds {max}, try increasing chunk size")]
+    TooManyChunks { count: u64, max: u64 },
+
+    #[error("Part size {size} is below minimum of {min} bytes")]
+    PartTooSmall { size: u64, min: u64 },
+
+    #[error("Expiration after {days} days exceeds max of {max} days")]
+    ExpirationTooLong { days: u64, max: u64 },
+
+    #[error("Size mismatch: expected {expected} bytes, got {actual}")]
+    SizeMismatch { expected: u64, actual: u64 },
+
+    #[error("Failed parts {parts:?} of multipart upload: {source}")]
+    PartsFailed { parts: Vec<i32>, source: Box<UtilsError> },
+
     #[error("Unexpected error")]
-    UnexpectedError(#[source] Report)
-}
\ No newline at end of file
+    UnexpectedError(#[source] Report),
+
+    #[error("{context}: {source}")]
+    Context { context: Box<ErrorContext>, source: Box<UtilsError> },
+}
+
+/// SdkErrors are boxed, they are several hundred bytes and would bloat every Result
+macro_rules! from_sdk_errors {
+    ($($(#[$attr:meta])* $variant:ident: $err:ty,)*) => {
+        $(
+            $(#[$attr])*
+            impl From<$err> for UtilsError {
+                fn from(err: $err) -> Self {
+                    UtilsError::$variant(Box::new(err))
+                }
+            }
+        )*
+    };
+}
+
+from_sdk_errors! {
+    GetObjectError: SdkError<GetObjectError>,
+    HeadObjectError: SdkError<HeadObjectError>,
+    ListObjectsV2Error: SdkError<ListObjectsV2Error>,
+    CreateMultipartUploadError: SdkError<CreateMultipartUploadError>,
+    CompleteMultipartUploadError: SdkError<CompleteMultipartUploadError>,
+    PutObjectError: SdkError<PutObjectError>,
+    UploadPartError: SdkError<UploadPartError>,
+    UploadPartCopyError: SdkError<UploadPartCopyError>,
+    CopyObjectError: SdkError<CopyObjectError>,
+    DeleteObjectError: SdkError<DeleteObjectError>,
+    DeleteObjectsError: SdkError<DeleteObjectsError>,
+    GetObjectTaggingError: SdkError<GetObjectTaggingError>,
+    PutObjectTaggingError: SdkError<PutObjectTaggingError>,
+    DeleteObjectTaggingError: SdkError<DeleteObjectTaggingError>,
+    PutObjectLegalHoldError: SdkError<PutObjectLegalHoldError>,
+    GetObjectLegalHoldError: SdkError<GetObjectLegalHoldError>,
+    GetObjectAttributesError: SdkError<GetObjectAttributesError>,
+    GetObjectRetentionError: SdkError<GetObjectRetentionError>,
+    PutObjectRetentionError: SdkError<PutObjectRetentionError>,
+    CreateBucketError: SdkError<CreateBucketError>,
+    DeleteBucketError: SdkError<DeleteBucketError>,
+    ListObjectVersionsError: SdkError<ListObjectVersionsError>,
+    ListMultipartUploadsError: SdkError<ListMultipartUploadsError>,
+    AbortMultipartUploadError: SdkError<AbortMultipartUploadError>,
+    HeadBucketError: SdkError<HeadBucketError>,
+    ListBucketsError: SdkError<ListBucketsError>,
+    PutBucketLifecycleConfigurationError: SdkError<PutBucketLifecycleConfigurationError>,
+    GetBucketLifecycleConfigurationError: SdkError<GetBucketLifecycleConfigurationError>,
+    GetBucketPolicyError: SdkError<GetBucketPolicyError>,
+    PutBucketPolicyError: SdkError<PutBucketPolicyError>,
+    GetBucketCorsError: SdkError<GetBucketCorsError>,
+    PutBucketCorsError: SdkError<PutBucketCorsError>,
+    DeleteBucketCorsError: SdkError<DeleteBucketCorsError>,
+    GetBucketLocationError: SdkError<GetBucketLocationError>,
+    PutPublicAccessBlockError: SdkError<PutPublicAccessBlockError>,
+    GetPublicAccessBlockError: SdkError<GetPublicAccessBlockError>,
+    PutBucketInventoryConfigurationError: SdkError<PutBucketInventoryConfigurationError>,
+    SelectObjectContentError: SdkError<SelectObjectContentError>,
+    SelectObjectContentEventStreamError: SdkError<SelectObjectContentEventStreamError, RawMessage>,
+    #[cfg(feature = "events")]
+    ReceiveMessageError: SdkError<ReceiveMessageError>,
+    #[cfg(feature = "events")]
+    DeleteMessageError: SdkError<DeleteMessageError>,
+    RestoreObjectError: SdkError<RestoreObjectError>,
+}
+
+/// Operation and object of failed call
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// AWS S3 operation or local step like GetObject or ReadFile
+    pub operation: &'static str,
+    pub bucket: Option<String>,
+    pub key: Option<String>,
+    /// Part of multipart upload or ranged download
+    pub part_number: Option<i32>,
+}
+
+impl ErrorContext {
+    pub fn new(operation: &'static str) -> Self {
+        Self {
+            operation,
+            ..Default::default()
+        }
+    }
+
+    /// Context of call on object
+    pub fn object(operation: &'static str, bucket: impl Into<String>, key: impl Into<String>) -> Self {
+        Self::new(operation).bucket(bucket).key(key)
+    }
+
+    pub fn bucket(mut self, bucket: impl Into<String>) -> Self {
+        self.bucket = Some(bucket.into());
+        self
+    }
+
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    pub fn part_number(mut self, part_number: i32) -> Self {
+        self.part_number = Some(part_number);
+        self
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.operation)?;
+        match (&self.bucket, &self.key) {
+            (Some(bucket), Some(key)) => write!(f, " s3://{}/{}", bucket, key)?,
+            (Some(bucket), None) => write!(f, " s3://{}", bucket)?,
+            (None, Some(key)) => write!(f, " {}", key)?,
+            (None, None) => {}
+        }
+        if let Some(part_number) = self.part_number {
+            write!(f, " part {}", part_number)?;
+        }
+        Ok(())
+    }
+}
+
+/// Attach ErrorContext to error of fallible call, context is built only on error
+pub trait ResultExt<T> {
+    fn with_context(self, context: impl FnOnce() -> ErrorContext) -> Result<T, UtilsError>;
+}
+
+impl<T, E: Into<UtilsError>> ResultExt<T> for Result<T, E> {
+    fn with_context(self, context: impl FnOnce() -> ErrorContext) -> Result<T, UtilsError> {
+        self.map_err(|err| err.into().context(context()))
+    }
+}
+
+impl UtilsError {
+    /// Wrap error with operation and object of failed call
+    pub fn context(self, context: ErrorContext) -> Self {
+        UtilsError::Context { context: Box::new(context), source: Box::new(self) }
+    }
+
+    /// Operation and object of failed call, innermost if error has several contexts
+    pub fn error_context(&self) -> Option<&ErrorContext> {
+        match self {
+            UtilsError::Context { context, source } => source.error_context().or(Some(context.as_ref())),
+            _ => None,
+        }
+    }
+
+    /// Error without context and failed parts, match on it instead of error itself
+    pub fn root(&self) -> &UtilsError {
+        match self {
+            UtilsError::Context { source, .. } | UtilsError::PartsFailed { source, .. } => source.root(),
+            err => err,
+        }
+    }
+
+    /// AWS request id (x-amz-request-id) of failed request, needed by AWS support
+    pub fn request_id(&self) -> Option<&str> {
+        self.sdk_error().and_then(|err| err.request_id())
+    }
+
+    /// AWS extended request id (x-amz-id-2) of failed request, needed by AWS support
+    pub fn extended_request_id(&self) -> Option<&str> {
+        self.sdk_error().and_then(|err| err.extended_request_id())
+    }
+
+    /// HTTP status code of failed AWS request
+    pub fn status_code(&self) -> Option<u16> {
+        self.sdk_error().and_then(|err| err.status_code())
+    }
+
+    /// AWS error code of failed request like NoSuchKey or SlowDown
+    pub fn error_code(&self) -> Option<&str> {
+        self.sdk_error().and_then(|err| err.code())
+    }
+
+    /// Check if error is transient: timeouts, connection and stream failures, throttling and server errors,
+    /// local io errors like missing file or denied permission are not. Retrying is up to caller as not every request is idempotent
+    pub fn is_retryable(&self) -> bool {
+        match self.root() {
+            UtilsError::IoError(err) => matches!(
+                err.kind(),
+                ErrorKind::Interrupted
+                    | ErrorKind::TimedOut
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::UnexpectedEof
+                    | ErrorKind::WouldBlock
+            ),
+            UtilsError::AWSSmithyError(_) => true,
+            _ => self.is_throttling() || self.sdk_error().is_some_and(|err| {
+                err.is_transient()
+                    || err.status_code().is_some_and(|status| status >= 500)
+                    || err.code().is_some_and(|code| RETRYABLE_CODES.contains(&code))
+            }),
+        }
+    }
+
+    /// Check if AWS asked to reduce request rate: 503 SlowDown, throttling error codes or 429 status
+    pub fn is_throttling(&self) -> bool {
+        self.sdk_error().is_some_and(|err| {
+            err.status_code() == Some(429) || err.code().is_some_and(|code| THROTTLING_CODES.contains(&code))
+        })
+    }
+
+    /// Replace NoSuchBucket error of AWS request with BucketNotFound
+    pub(crate) fn or_bucket_not_found(self, bucket: &str) -> Self {
+        match self.error_code() {
+            Some("NoSuchBucket") => UtilsError::BucketNotFound(bucket.to_string()),
+            _ => self,
+        }
+    }
+
+    fn sdk_error(&self) -> Option<&dyn SdkErrorMetadata> {
+        match self.root() {
+            UtilsError::GetObjectError(err) => Some(err.as_ref()),
+            UtilsError::HeadObjectError(err) => Some(err.as_ref()),
+            UtilsError::ListObjectsV2Error(err) => Some(err.as_ref()),
+            UtilsError::CreateMultipartUploadError(err) => Some(err.as_ref()),
+            UtilsError::CompleteMultipartUploadError(err) => Some(err.as_ref()),
+            UtilsError::PutObjectError(err) => Some(err.as_ref()),
+            UtilsError::UploadPartError(err) => Some(err.as_ref()),
+            UtilsError::UploadPartCopyError(err) => Some(err.as_ref()),
+            UtilsError::CopyObjectError(err) => Some(err.as_ref()),
+            UtilsError::DeleteObjectError(err) => Some(err.as_ref()),
+            UtilsError::DeleteObjectsError(err) => Some(err.as_ref()),
+            UtilsError::GetObjectTaggingError(err) => Some(err.as_ref()),
+            UtilsError::PutObjectTaggingError(err) => Some(err.as_ref()),
+            UtilsError::DeleteObjectTaggingError(err) => Some(err.as_ref()),
+            UtilsError::PutObjectLegalHoldError(err) => Some(err.as_ref()),
+            UtilsError::GetObjectLegalHoldError(err) => Some(err.as_ref()),
+            UtilsError::GetObjectAttributesError(err) => Some(err.as_ref()),
+            UtilsError::GetObjectRetentionError(err) => Some(err.as_ref()),
+            UtilsError::PutObjectRetentionError(err) => Some(err.as_ref()),
+            UtilsError::CreateBucketError(err) => Some(err.as_ref()),
+            UtilsError::DeleteBucketError(err) => Some(err.as_ref()),
+            UtilsError::ListObjectVersionsError(err) => Some(err.as_ref()),
+            UtilsError::ListMultipartUploadsError(err) => Some(err.as_ref()),
+            UtilsError::AbortMultipartUploadError(err) => Some(err.as_ref()),
+            UtilsError::HeadBucketError(err) => Some(err.as_ref()),
+            UtilsError::ListBucketsError(err) => Some(err.as_ref()),
+            UtilsError::PutBucketLifecycleConfigurationError(err) => Some(err.as_ref()),
+            UtilsError::GetBucketLifecycleConfigurationError(err) => Some(err.as_ref()),
+            UtilsError::GetBucketPolicyError(err) => Some(err.as_ref()),
+            UtilsError::PutBucketPolicyError(err) => Some(err.as_ref()),
+            UtilsError::GetBucketCorsError(err) => Some(err.as_ref()),
+            UtilsError::PutBucketCorsError(err) => Some(err.as_ref()),
+            UtilsError::DeleteBucketCorsError(err) => Some(err.as_ref()),
+            UtilsError::GetBucketLocationError(err) => Some(err.as_ref()),
+            UtilsError::PutPublicAccessBlockError(err) => Some(err.as_ref()),
+            UtilsError::GetPublicAccessBlockError(err) => Some(err.as_ref()),
+            UtilsError::PutBucketInventoryConfigurationError(err) => Some(err.as_ref()),
+            UtilsError::SelectObjectContentError(err) => Some(err.as_ref()),
+            #[cfg(feature = "events")]
+            UtilsError::ReceiveMessageError(err) => Some(err.as_ref()),
+            #[cfg(feature = "events")]
+            UtilsError::DeleteMessageError(err) => Some(err.as_ref()),
+            UtilsError::RestoreObjectError(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Metadata of AWS SDK error of any operation
+trait SdkErrorMetadata {
+    fn request_id(&self) -> Option<&str>;
+    fn extended_request_id(&self) -> Option<&str>;
+    fn status_code(&self) -> Option<u16>;
+    fn code(&self) -> Option<&str>;
+    fn is_transient(&self) -> bool;
+}
+
+impl<E: ProvideErrorMetadata> SdkErrorMetadata for SdkError<E, HttpResponse> {
+    fn request_id(&self) -> Option<&str> {
+        RequestId::request_id(self)
+    }
+
+    fn extended_request_id(&self) -> Option<&str> {
+        RequestIdExt::extended_request_id(self)
+    }
+
+    fn status_code(&self) -> Option<u16> {
+        self.raw_response().map(|res| res.status().as_u16())
+    }
+
+    fn code(&self) -> Option<&str> {
+        self.as_service_error().and_then(|err| err.code())
+    }
+
+    fn is_transient(&self) -> bool {
+        matches!(self, SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) | SdkError::ResponseError(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_s3::error::ErrorMetadata;
+    use aws_smithy_runtime_api::http::StatusCode;
+    use aws_smithy_types::body::SdkBody;
+
+    use super::*;
+
+    fn service_error(code: &str, status: u16) -> UtilsError {
+        let err = GetObjectError::generic(ErrorMetadata::builder().code(code).build());
+        let res = HttpResponse::new(StatusCode::try_from(status).unwrap(), SdkBody::empty());
+        UtilsError::from(SdkError::service_error(err, res)).context(ErrorContext::object("GetObject", "bucket", "key"))
+    }
+
+    #[test]
+    fn service_errors_retryable_and_throttling() {
+        let err = service_error("SlowDown", 503);
+        assert!(err.is_retryable() && err.is_throttling());
+        assert_eq!(err.error_code(), Some("SlowDown"));
+        assert_eq!(err.status_code(), Some(503));
+
+        let err = service_error("TooManyRequestsException", 429);
+        assert!(err.is_retryable() && err.is_throttling());
+        let err = service_error("InternalError", 500);
+        assert!(err.is_retryable() && !err.is_throttling());
+        let err = service_error("RequestTimeout", 400);
+        assert!(err.is_retryable() && !err.is_throttling());
+
+        for (code, status) in [("NoSuchKey", 404), ("AccessDenied", 403), ("InvalidRequest", 400)] {
+            let err = service_error(code, status);
+            assert!(!err.is_retryable() && !err.is_throttling(), "{}", code);
+        }
+    }
+
+    #[test]
+    fn io_errors_retryable_only_if_transient() {
+        for kind in [ErrorKind::Interrupted, ErrorKind::TimedOut, ErrorKind::ConnectionReset, ErrorKind::UnexpectedEof] {
+            assert!(UtilsError::from(IoError::from(kind)).is_retryable(), "{:?}", kind);
+        }
+        for kind in [ErrorKind::NotFound, ErrorKind::PermissionDenied, ErrorKind::InvalidData] {
+            assert!(!UtilsError::from(IoError::from(kind)).is_retryable(), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn context_keeps_retryable() {
+        let err = UtilsError::from(IoError::from(ErrorKind::TimedOut)).context(ErrorContext::object("GetObject", "bucket", "key"));
+        assert!(err.is_retryable());
+        assert!(!err.is_throttling());
+        assert!(!UtilsError::MissingUploadId("s3://bucket/key".to_string()).is_retryable());
+    }
+
+    #[test]
+    fn error_is_small() {
+        assert!(std::mem::size_of::<UtilsError>() <= 64, "{}", std::mem::size_of::<UtilsError>());
+    }
+}
@@ -0,0 +1,105 @@
+use aws_sdk_s3::Client;
+use color_eyre::eyre::eyre;
+
+use crate::utils::{head_object, S3Path, S3Writer};
+use crate::error::UtilsError;
+
+const MANIFEST_CONTENT_TYPE: &str = "text/csv";
+
+/// Object of S3 Batch Operations manifest, version is optional
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BatchObject {
+    pub path: S3Path,
+    pub version_id: Option<String>,
+}
+
+impl BatchObject {
+    pub fn new(path: S3Path) -> Self {
+        Self { path, version_id: None }
+    }
+
+    pub fn version_id(mut self, version_id: impl Into<String>) -> Self {
+        self.version_id = Some(version_id.into());
+        self
+    }
+}
+
+impl From<S3Path> for BatchObject {
+    fn from(path: S3Path) -> Self {
+        Self::new(path)
+    }
+}
+
+/// Location of uploaded manifest as needed by S3 Control CreateJob manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchManifest {
+    /// ARN of manifest object
+    pub object_arn: String,
+    /// ETag of manifest object without quotes
+    pub e_tag: String,
+    /// S3BatchOperations_CSV_20180820 fields: Bucket, Key and VersionId if any object has version
+    pub fields: Vec<&'static str>,
+    /// Number of objects, None for existing manifest
+    pub objects: Option<u64>,
+}
+
+/// Write S3 Batch Operations CSV manifest of objects to dest, keys are url encoded as required,
+/// VersionId column is written if any object has version
+pub async fn write_batch_manifest(
+    client: Client,
+    objects: impl IntoIterator<Item = impl Into<BatchObject>>,
+    dest: &S3Path,
+) -> Result<BatchManifest, UtilsError> {
+    let objects: Vec<BatchObject> = objects.into_iter().map(Into::into).collect();
+    let with_versions = objects.iter().any(|object| object.version_id.is_some());
+
+    let mut writer = S3Writer::new(client, &dest.bucket, &dest.key).content_type(MANIFEST_CONTENT_TYPE);
+    for object in &objects {
+        let mut line = format!("{},{}", object.path.bucket, urlencoding::encode(&object.path.key));
+        if with_versions {
+            line.push(',');
+            line.push_str(object.version_id.as_deref().unwrap_or_default());
+        }
+        line.push('\n');
+        writer.write(line.as_bytes()).await?;
+    }
+    let report = writer.finish().await?;
+    let e_tag = report
+        .e_tag
+        .ok_or_else(|| UtilsError::MissingETag(dest.uri()))?;
+
+    Ok(BatchManifest {
+        object_arn: object_arn(dest),
+        e_tag: e_tag.trim_matches('"').to_string(),
+        fields: manifest_fields(with_versions),
+        objects: Some(objects.len() as u64),
+    })
+}
+
+/// Get location of existing manifest, fields are given as manifest has no header
+pub async fn batch_manifest_location(client: Client, manifest: &S3Path, with_versions: bool) -> Result<BatchManifest, UtilsError> {
+    let head = head_object(client, &manifest.bucket, &manifest.key)
+        .await?
+        .ok_or_else(|| UtilsError::UnexpectedError(eyre!("Manifest not found: {}", manifest.uri())))?;
+    let e_tag = head
+        .e_tag()
+        .ok_or_else(|| UtilsError::MissingETag(manifest.uri()))?;
+
+    Ok(BatchManifest {
+        object_arn: object_arn(manifest),
+        e_tag: e_tag.trim_matches('"').to_string(),
+        fields: manifest_fields(with_versions),
+        objects: None,
+    })
+}
+
+fn object_arn(path: &S3Path) -> String {
+    format!("arn:aws:s3:::{}/{}", path.bucket, path.key)
+}
+
+fn manifest_fields(with_versions: bool) -> Vec<&'static str> {
+    match with_versions {
+        true => vec!["Bucket", "Key", "VersionId"],
+        false => vec!["Bucket", "Key"],
+    }
+}
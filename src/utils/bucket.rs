@@ -0,0 +1,465 @@
+use std::collections::HashMap;
+#[cfg(feature = "sync")]
+use std::path::Path;
+
+use aws_sdk_s3::{error::ProvideErrorMetadata, operation::{create_bucket::CreateBucketError, head_bucket::{HeadBucketError, HeadBucketOutput}}, types::{BucketLocationConstraint, CorsConfiguration, CorsRule as SdkCorsRule, CreateBucketConfiguration, Delete, ObjectIdentifier, PublicAccessBlockConfiguration}, Client};
+use aws_smithy_types::DateTime;
+use color_eyre::eyre::eyre;
+use serde_json::Value;
+
+use crate::utils::{list_objects_info, ListOptions, ObjectInfo, S3Key, S3Object};
+#[cfg(feature = "sync")]
+use crate::utils::{sync_up, S3Path, SyncOptions, SyncReport};
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+/// Create bucket in region, succeeds if bucket already exists and is owned by us
+pub async fn create_bucket(client: Client, bucket: &str, region: &str) -> Result<(), UtilsError> {
+    // us-east-1 is the default location and is rejected as explicit location constraint
+    let configuration = match region {
+        "us-east-1" => None,
+        region => Some(
+            CreateBucketConfiguration::builder()
+                .location_constraint(BucketLocationConstraint::from(region))
+                .build()
+        ),
+    };
+
+    let res = client
+        .create_bucket()
+        .bucket(bucket)
+        .set_create_bucket_configuration(configuration)
+        .send()
+        .await;
+
+    match res {
+        Ok(_) => Ok(()),
+        Err(sdk_err) => match sdk_err.as_service_error() {
+            Some(CreateBucketError::BucketAlreadyOwnedByYou(_)) => Ok(()),
+            _ => Err(UtilsError::from(sdk_err).context(ErrorContext::new("CreateBucket").bucket(bucket))),
+        }
+    }
+}
+
+/// Get bucket region, empty location constraint means us-east-1 and legacy EU means eu-west-1
+pub async fn get_bucket_region(client: Client, bucket: &str) -> Result<String, UtilsError> {
+    let res = client
+        .get_bucket_location()
+        .bucket(bucket)
+        .send()
+        .await
+        .with_context(|| ErrorContext::new("GetBucketLocation").bucket(bucket))?;
+
+    let region = match res.location_constraint().map(|val| val.as_str()) {
+        None | Some("") => "us-east-1",
+        Some("EU") => "eu-west-1",
+        Some(region) => region,
+    };
+
+    Ok(region.to_string())
+}
+
+/// Get AWS HeadBucketOutput, None if bucket doesn't exist,
+/// AccessDenied error if bucket exists but access is denied
+pub async fn head_bucket(client: Client, bucket: &str) -> Result<Option<HeadBucketOutput>, UtilsError> {
+    let res = client
+        .head_bucket()
+        .bucket(bucket)
+        .send()
+        .await;
+
+    match res {
+        Ok(res) => Ok(Some(res)),
+        Err(sdk_err) => match sdk_err.as_service_error() {
+            Some(HeadBucketError::NotFound(_)) => Ok(None),
+            _ if sdk_err.raw_response().is_some_and(|res| res.status().as_u16() == 403) => {
+                Err(UtilsError::AccessDenied { bucket: bucket.to_string() })
+            }
+            _ => Err(UtilsError::from(sdk_err).context(ErrorContext::new("HeadBucket").bucket(bucket))),
+        }
+    }
+}
+
+/// Check if bucket exists, error if bucket exists but access is denied
+pub async fn bucket_exists(client: Client, bucket: &str) -> Result<bool, UtilsError> {
+    let res = head_bucket(client, bucket).await?;
+
+    Ok(res.is_some())
+}
+
+/// AWS S3 bucket info
+#[derive(Debug, Clone, PartialEq)]
+pub struct BucketInfo {
+    pub name: String,
+    pub creation_date: Option<DateTime>,
+    pub region: Option<String>,
+}
+
+/// Get buckets names and creation dates, optionally filtered by name prefix and region
+pub async fn list_buckets(client: Client, prefix: Option<&str>, region: Option<&str>) -> Result<Vec<BucketInfo>, UtilsError> {
+    let mut stream = client
+        .list_buckets()
+        .set_prefix(prefix.map(|val| val.to_string()))
+        .set_bucket_region(region.map(|val| val.to_string()))
+        .into_paginator()
+        .send();
+
+    let mut buckets = Vec::new();
+    while let Some(res) = stream.next().await.transpose().with_context(|| ErrorContext::new("ListBuckets"))? {
+        for bucket in res.buckets() {
+            if let Some(name) = bucket.name() {
+                buckets.push(BucketInfo {
+                    name: name.to_string(),
+                    creation_date: bucket.creation_date().cloned(),
+                    region: bucket.bucket_region().map(|val| val.to_string()),
+                });
+            }
+        }
+    }
+
+    Ok(buckets)
+}
+
+/// Get bucket policy as JSON, None if bucket has no policy
+pub async fn get_bucket_policy(client: Client, bucket: &str) -> Result<Option<Value>, UtilsError> {
+    let res = client
+        .get_bucket_policy()
+        .bucket(bucket)
+        .send()
+        .await;
+
+    let res = match res {
+        Ok(res) => res,
+        Err(sdk_err) => match sdk_err.as_service_error().and_then(|err| err.code()) {
+            Some("NoSuchBucketPolicy") => return Ok(None),
+            _ => return Err(UtilsError::from(sdk_err).context(ErrorContext::new("GetBucketPolicy").bucket(bucket))),
+        }
+    };
+
+    match res.policy() {
+        Some(policy) => Ok(Some(serde_json::from_str(policy)?)),
+        None => Ok(None),
+    }
+}
+
+/// Replace bucket policy
+pub async fn put_bucket_policy(client: Client, bucket: &str, policy: &Value) -> Result<(), UtilsError> {
+    client
+        .put_bucket_policy()
+        .bucket(bucket)
+        .policy(serde_json::to_string(policy)?)
+        .send()
+        .await
+        .with_context(|| ErrorContext::new("PutBucketPolicy").bucket(bucket))?;
+
+    Ok(())
+}
+
+/// Bucket CORS rule
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorsRule {
+    pub id: Option<String>,
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    pub expose_headers: Vec<String>,
+    pub max_age_seconds: Option<i32>,
+}
+
+impl From<&SdkCorsRule> for CorsRule {
+    fn from(value: &SdkCorsRule) -> Self {
+        Self {
+            id: value.id().map(|val| val.to_string()),
+            allowed_origins: value.allowed_origins().to_vec(),
+            allowed_methods: value.allowed_methods().to_vec(),
+            allowed_headers: value.allowed_headers().to_vec(),
+            expose_headers: value.expose_headers().to_vec(),
+            max_age_seconds: value.max_age_seconds(),
+        }
+    }
+}
+
+/// Get bucket CORS rules, empty if bucket has no CORS configuration
+pub async fn get_bucket_cors(client: Client, bucket: &str) -> Result<Vec<CorsRule>, UtilsError> {
+    let res = client
+        .get_bucket_cors()
+        .bucket(bucket)
+        .send()
+        .await;
+
+    match res {
+        Ok(res) => Ok(res.cors_rules().iter().map(CorsRule::from).collect()),
+        Err(sdk_err) => match sdk_err.as_service_error().and_then(|err| err.code()) {
+            Some("NoSuchCORSConfiguration") => Ok(Vec::new()),
+            _ => Err(UtilsError::from(sdk_err).context(ErrorContext::new("GetBucketCors").bucket(bucket))),
+        }
+    }
+}
+
+/// Replace bucket CORS configuration with rules
+pub async fn put_bucket_cors(client: Client, bucket: &str, rules: &[CorsRule]) -> Result<(), UtilsError> {
+    let rules = rules
+        .iter()
+        .map(|rule| {
+            SdkCorsRule::builder()
+                .set_id(rule.id.clone())
+                .set_allowed_origins(Some(rule.allowed_origins.clone()))
+                .set_allowed_methods(Some(rule.allowed_methods.clone()))
+                .set_allowed_headers(Some(rule.allowed_headers.clone()))
+                .set_expose_headers(Some(rule.expose_headers.clone()))
+                .set_max_age_seconds(rule.max_age_seconds)
+                .build()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let configuration = CorsConfiguration::builder()
+        .set_cors_rules(Some(rules))
+        .build()?;
+
+    client
+        .put_bucket_cors()
+        .bucket(bucket)
+        .cors_configuration(configuration)
+        .send()
+        .await
+        .with_context(|| ErrorContext::new("PutBucketCors").bucket(bucket))?;
+
+    Ok(())
+}
+
+/// Delete bucket CORS configuration
+pub async fn delete_bucket_cors(client: Client, bucket: &str) -> Result<(), UtilsError> {
+    client
+        .delete_bucket_cors()
+        .bucket(bucket)
+        .send()
+        .await
+        .with_context(|| ErrorContext::new("DeleteBucketCors").bucket(bucket))?;
+
+    Ok(())
+}
+
+/// Bucket public access block settings, default blocks all public access
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicAccessBlock {
+    pub block_public_acls: bool,
+    pub ignore_public_acls: bool,
+    pub block_public_policy: bool,
+    pub restrict_public_buckets: bool,
+}
+
+impl Default for PublicAccessBlock {
+    fn default() -> Self {
+        Self {
+            block_public_acls: true,
+            ignore_public_acls: true,
+            block_public_policy: true,
+            restrict_public_buckets: true,
+        }
+    }
+}
+
+/// Set bucket public access block configuration
+pub async fn put_public_access_block(client: Client, bucket: &str, config: PublicAccessBlock) -> Result<(), UtilsError> {
+    let configuration = PublicAccessBlockConfiguration::builder()
+        .block_public_acls(config.block_public_acls)
+        .ignore_public_acls(config.ignore_public_acls)
+        .block_public_policy(config.block_public_policy)
+        .restrict_public_buckets(config.restrict_public_buckets)
+        .build();
+
+    client
+        .put_public_access_block()
+        .bucket(bucket)
+        .public_access_block_configuration(configuration)
+        .send()
+        .await
+        .with_context(|| ErrorContext::new("PutPublicAccessBlock").bucket(bucket))?;
+
+    Ok(())
+}
+
+/// Get bucket public access block configuration, None if bucket has no configuration
+pub async fn get_public_access_block(client: Client, bucket: &str) -> Result<Option<PublicAccessBlock>, UtilsError> {
+    let res = client
+        .get_public_access_block()
+        .bucket(bucket)
+        .send()
+        .await;
+
+    let res = match res {
+        Ok(res) => res,
+        Err(sdk_err) => match sdk_err.as_service_error().and_then(|err| err.code()) {
+            Some("NoSuchPublicAccessBlockConfiguration") => return Ok(None),
+            _ => return Err(UtilsError::from(sdk_err).context(ErrorContext::new("GetPublicAccessBlock").bucket(bucket))),
+        }
+    };
+
+    let config = res.public_access_block_configuration().map(|config| PublicAccessBlock {
+        block_public_acls: config.block_public_acls().unwrap_or(false),
+        ignore_public_acls: config.ignore_public_acls().unwrap_or(false),
+        block_public_policy: config.block_public_policy().unwrap_or(false),
+        restrict_public_buckets: config.restrict_public_buckets().unwrap_or(false),
+    });
+
+    Ok(config)
+}
+
+/// Delete bucket, if force is set bucket is emptied first (objects, versions, delete markers and incomplete multipart uploads)
+pub async fn delete_bucket(client: Client, bucket: &str, force: bool) -> Result<(), UtilsError> {
+    if force {
+        empty_bucket(client.clone(), bucket).await?;
+    }
+
+    client
+        .delete_bucket()
+        .bucket(bucket)
+        .send()
+        .await
+        .with_context(|| ErrorContext::new("DeleteBucket").bucket(bucket))?;
+
+    Ok(())
+}
+
+/// Delete all object versions, delete markers and incomplete multipart uploads in bucket
+async fn empty_bucket(client: Client, bucket: &str) -> Result<(), UtilsError> {
+    loop {
+        let res = client
+            .list_object_versions()
+            .bucket(bucket)
+            .send()
+            .await
+            .with_context(|| ErrorContext::new("ListObjectVersions").bucket(bucket))?;
+
+        let versions = res
+            .versions()
+            .iter()
+            .map(|val| (val.key(), val.version_id()));
+        let delete_markers = res
+            .delete_markers()
+            .iter()
+            .map(|val| (val.key(), val.version_id()));
+        let objects = versions
+            .chain(delete_markers)
+            .filter_map(|(key, version_id)| key.map(|key| (key, version_id)))
+            .map(|(key, version_id)| {
+                ObjectIdentifier::builder()
+                    .key(key)
+                    .set_version_id(version_id.map(|val| val.to_string()))
+                    .build()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if objects.is_empty() {
+            break;
+        }
+
+        let delete = Delete::builder()
+            .set_objects(Some(objects))
+            .quiet(true)
+            .build()?;
+        let res = client
+            .delete_objects()
+            .bucket(bucket)
+            .delete(delete)
+            .send()
+            .await
+            .with_context(|| ErrorContext::new("DeleteObjects").bucket(bucket))?;
+
+        if let Some(err) = res.errors().first() {
+            let err = eyre!("Failed deleting {} versions, first: {:?}", res.errors().len(), err.key());
+            return Err(UtilsError::UnexpectedError(err));
+        }
+    }
+
+    loop {
+        let res = client
+            .list_multipart_uploads()
+            .bucket(bucket)
+            .send()
+            .await
+            .with_context(|| ErrorContext::new("ListMultipartUploads").bucket(bucket))?;
+
+        if res.uploads().is_empty() {
+            break;
+        }
+        for upload in res.uploads() {
+            client
+                .abort_multipart_upload()
+                .bucket(bucket)
+                .set_key(upload.key().map(|val| val.to_string()))
+                .set_upload_id(upload.upload_id().map(|val| val.to_string()))
+                .send()
+                .await
+                .with_context(|| ErrorContext::object("AbortMultipartUpload", bucket, upload.key().unwrap_or_default()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle to AWS S3 bucket
+#[derive(Debug, Clone)]
+pub struct Bucket {
+    client: Client,
+    name: String,
+}
+
+/// Objects count and size under prefix
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PrefixStats {
+    pub objects: u64,
+    pub size: i64,
+    /// Total size by storage class
+    pub size_by_storage_class: HashMap<String, i64>,
+    pub last_modified: Option<DateTime>,
+}
+
+impl PrefixStats {
+    /// Count listed object
+    pub fn add(&mut self, file: &ObjectInfo) {
+        self.objects += 1;
+        self.size += file.size;
+        let storage_class = file.storage_class.clone().unwrap_or_else(|| "STANDARD".to_string());
+        *self.size_by_storage_class.entry(storage_class).or_default() += file.size;
+        if file.last_modified > self.last_modified {
+            self.last_modified = file.last_modified;
+        }
+    }
+}
+
+impl Bucket {
+    pub fn new(client: Client, name: impl Into<String>) -> Self {
+        Self {
+            client,
+            name: name.into(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get handle to object in this bucket
+    pub fn object(&self, key: impl Into<S3Key>) -> S3Object {
+        S3Object::new(self.client.clone(), self.name.clone(), key)
+    }
+
+    /// Get files info under prefix
+    pub async fn list(&self, prefix: &str) -> Result<Vec<ObjectInfo>, UtilsError> {
+        list_objects_info(self.client.clone(), &self.name, prefix, &ListOptions::default()).await
+    }
+
+    /// Sync local dir to prefix
+    #[cfg(feature = "sync")]
+    pub async fn sync_from(&self, local_dir: impl AsRef<Path>, prefix: &str, opts: &SyncOptions) -> Result<SyncReport, UtilsError> {
+        sync_up(self.client.clone(), local_dir, &S3Path::new(self.name.clone(), prefix), opts).await
+    }
+
+    /// Get objects count and size under prefix
+    pub async fn stats(&self, prefix: &str) -> Result<PrefixStats, UtilsError> {
+        let mut stats = PrefixStats::default();
+        for file in self.list(prefix).await? {
+            stats.add(&file);
+        }
+
+        Ok(stats)
+    }
+}
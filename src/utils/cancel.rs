@@ -0,0 +1,16 @@
+use std::future::Future;
+
+pub use tokio_util::sync::CancellationToken;
+
+use crate::error::UtilsError;
+
+/// Run future until token is cancelled, cancelled future is dropped and Cancelled error returned
+pub(crate) async fn cancellable<T, F>(token: Option<&CancellationToken>, fut: F) -> Result<T, UtilsError>
+where
+    F: Future<Output = Result<T, UtilsError>>,
+{
+    match token {
+        Some(token) => token.run_until_cancelled(fut).await.unwrap_or(Err(UtilsError::Cancelled)),
+        None => fut.await,
+    }
+}
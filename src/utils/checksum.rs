@@ -0,0 +1,176 @@
+use std::path::Path;
+
+use aws_sdk_s3::{primitives::ByteStream, Client};
+use color_eyre::eyre::eyre;
+use md5::{Digest, Md5};
+use sha2::Sha256;
+use tokio::{fs::File, io::AsyncReadExt};
+use tracing::debug;
+
+use crate::utils::{get_aws_object_with_options, read_file, GetOptions, S3Key, READ_BUF_SIZE, SIDECAR_EXTENSION};
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+/// Kind of AWS S3 ETag
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EtagKind {
+    /// MD5 of object uploaded in single part
+    SinglePart { md5: String },
+    /// MD5 of part MD5s of object uploaded with multipart upload
+    Multipart { md5: String, parts: u32 },
+    /// ETag not matching any known format
+    Unknown(String),
+}
+
+/// Parse AWS S3 ETag, surrounding quotes are ignored
+pub fn parse_etag(etag: &str) -> EtagKind {
+    let etag = etag.trim_matches('"');
+    let is_md5 = |val: &str| val.len() == 32 && val.chars().all(|c| c.is_ascii_hexdigit());
+    match etag.split_once('-') {
+        Some((md5, parts)) if is_md5(md5) => match parts.parse() {
+            Ok(parts) => EtagKind::Multipart { md5: md5.to_lowercase(), parts },
+            Err(_) => EtagKind::Unknown(etag.to_string()),
+        },
+        None if is_md5(etag) => EtagKind::SinglePart { md5: etag.to_lowercase() },
+        _ => EtagKind::Unknown(etag.to_string()),
+    }
+}
+
+/// Compute AWS S3 ETag of local file: MD5 if chunk_size is None (single part upload)
+/// or MD5 of chunk MD5s with number of chunks (multipart upload), chunk_size must not be zero
+pub async fn compute_s3_etag(path: impl AsRef<Path>, chunk_size: Option<u64>) -> Result<String, UtilsError> {
+    if chunk_size == Some(0) {
+        return Err(UtilsError::PartTooSmall { size: 0, min: 1 });
+    }
+    let mut file = File::open(path).await?;
+    let mut buf = vec![0u8; READ_BUF_SIZE];
+    let mut hasher = Md5::new();
+    let mut part_digests = Vec::new();
+    let mut part_len = 0;
+    loop {
+        let to_read = match chunk_size {
+            Some(chunk_size) => (chunk_size - part_len).min(buf.len() as u64) as usize,
+            None => buf.len(),
+        };
+        let n = file.read(&mut buf[..to_read]).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        part_len += n as u64;
+        if Some(part_len) == chunk_size {
+            part_digests.push(hasher.finalize_reset());
+            part_len = 0;
+        }
+    }
+
+    if chunk_size.is_none() {
+        return Ok(format!("{:x}", hasher.finalize()));
+    }
+    if part_len > 0 || part_digests.is_empty() {
+        part_digests.push(hasher.finalize_reset());
+    }
+    for digest in &part_digests {
+        hasher.update(digest);
+    }
+
+    Ok(format!("{:x}-{}", hasher.finalize(), part_digests.len()))
+}
+
+/// Compute hex SHA-256 of local file
+pub async fn compute_sha256(path: impl AsRef<Path>) -> Result<String, UtilsError> {
+    let mut file = File::open(path).await?;
+    let mut buf = vec![0u8; READ_BUF_SIZE];
+    let mut hasher = Sha256::new();
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Get key of sidecar checksum object of key
+pub fn sidecar_key(key: &str) -> String {
+    format!("{}.{}", key, SIDECAR_EXTENSION)
+}
+
+/// Write <key>.sha256 sidecar object in sha256sum format
+pub async fn write_sidecar(client: Client, bucket: &str, key: impl Into<S3Key>, sha256: &str) -> Result<(), UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let file_name = key.rsplit('/').next().unwrap_or(key);
+    let body = format!("{}  {}\n", sha256, file_name);
+
+    client
+        .put_object()
+        .bucket(bucket)
+        .key(sidecar_key(key))
+        .content_type("text/plain")
+        .body(ByteStream::from(body.into_bytes()))
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("PutObject", bucket, sidecar_key(key)))?;
+
+    Ok(())
+}
+
+/// Hash object and compare with SHA-256 of <key>.sha256 sidecar object
+pub async fn verify_with_sidecar(client: Client, bucket: &str, key: impl Into<S3Key>) -> Result<bool, UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let sidecar = read_file(client.clone(), bucket, &sidecar_key(key)).await?;
+    let expected = String::from_utf8_lossy(&sidecar)
+        .split_whitespace()
+        .next()
+        .map(|val| val.to_lowercase())
+        .ok_or_else(|| UtilsError::UnexpectedError(eyre!("Empty sidecar of: {}/{}", bucket, key)))?;
+
+    let mut object = get_aws_object_with_options(client, bucket, key, &GetOptions::default()).await?;
+    let mut hasher = Sha256::new();
+    while let Some(bytes) = object.body.try_next().await? {
+        hasher.update(&bytes);
+    }
+    let actual = format!("{:x}", hasher.finalize());
+    debug!(bucket, key, expected, actual, "verified sidecar checksum");
+
+    Ok(actual == expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_etag_kinds() {
+        let md5 = "900150983cd24fb0d6963f7d28e17f72";
+        assert_eq!(parse_etag(&format!("\"{}\"", md5)), EtagKind::SinglePart { md5: md5.to_string() });
+        assert_eq!(parse_etag(&md5.to_uppercase()), EtagKind::SinglePart { md5: md5.to_string() });
+        assert_eq!(parse_etag(&format!("\"{}-12\"", md5)), EtagKind::Multipart { md5: md5.to_string(), parts: 12 });
+        assert_eq!(parse_etag(&format!("{}-x", md5)), EtagKind::Unknown(format!("{}-x", md5)));
+        assert_eq!(parse_etag("\"abc\""), EtagKind::Unknown("abc".to_string()));
+        assert_eq!(parse_etag(""), EtagKind::Unknown(String::new()));
+    }
+
+    #[tokio::test]
+    async fn compute_s3_etag_single_and_multipart() {
+        let path = std::env::temp_dir().join(format!("checksum-test-{}.txt", std::process::id()));
+        tokio::fs::write(&path, b"abcdef").await.unwrap();
+
+        let single = compute_s3_etag(&path, None).await;
+        let multipart = compute_s3_etag(&path, Some(4)).await;
+        let zero_chunk = compute_s3_etag(&path, Some(0)).await;
+        let sha256 = compute_sha256(&path).await;
+        tokio::fs::remove_file(&path).await.unwrap();
+
+        assert_eq!(single.unwrap(), "e80b5017098950fc58aad83c8c14978e");
+        let mut hasher = Md5::new();
+        hasher.update(Md5::digest(b"abcd"));
+        hasher.update(Md5::digest(b"ef"));
+        assert_eq!(multipart.unwrap(), format!("{:x}-2", hasher.finalize()));
+        assert!(matches!(zero_chunk, Err(UtilsError::PartTooSmall { size: 0, .. })));
+        assert_eq!(sha256.unwrap(), "bef57ec7f53a6d40beb640a780a639c83bc29ac8a9816f1fc6c5c6dcd93c4721");
+    }
+}
@@ -0,0 +1,114 @@
+use aws_config::{BehaviorVersion, Region, retry::RetryConfig};
+use aws_sdk_s3::{
+    config::{interceptors::BeforeTransmitInterceptorContextMut, Builder, ConfigBag, Intercept, RuntimeComponents, SharedInterceptor},
+    error::BoxError,
+    Client,
+};
+
+use crate::utils::{ClientOptions, AWS_MAX_RETRIES};
+
+/// Get AWS Client
+pub async fn get_aws_client(region: &str) -> Client {
+    get_aws_client_with_interceptors(region, Vec::new()).await
+}
+
+/// Get AWS Client running interceptors on every request
+pub async fn get_aws_client_with_interceptors(region: &str, interceptors: Vec<SharedInterceptor>) -> Client {
+    let region = Region::new(region.to_string());
+
+    let sdk_config = aws_config::defaults(BehaviorVersion::latest())
+        .region(region)
+        .load()
+        .await;
+
+    let mut config_builder = Builder::from(&sdk_config)
+        .retry_config(RetryConfig::standard().with_max_attempts(AWS_MAX_RETRIES));
+    for interceptor in interceptors {
+        config_builder.push_interceptor(interceptor);
+    }
+
+    let config = config_builder.build();
+   
+    Client::from_conf(config)
+}
+
+/// Get AWS Client with region, profile and endpoint overrides
+pub async fn get_aws_client_with_options(opts: &ClientOptions) -> Client {
+    let mut loader = aws_config::defaults(BehaviorVersion::latest());
+    if let Some(region) = &opts.region {
+        loader = loader.region(Region::new(region.clone()));
+    }
+    if let Some(profile) = &opts.profile {
+        loader = loader.profile_name(profile);
+    }
+    if let Some(endpoint_url) = &opts.endpoint_url {
+        loader = loader.endpoint_url(endpoint_url);
+    }
+    let sdk_config = loader.load().await;
+
+    let config = Builder::from(&sdk_config)
+        .retry_config(RetryConfig::standard().with_max_attempts(AWS_MAX_RETRIES))
+        .force_path_style(opts.force_path_style)
+        .build();
+
+    Client::from_conf(config)
+}
+
+/// Get copy of client running interceptor on every request in addition to its own
+pub fn with_interceptor(client: &Client, interceptor: impl Intercept + 'static) -> Client {
+    let config = client
+        .config()
+        .to_builder()
+        .interceptor(interceptor)
+        .build();
+
+    Client::from_conf(config)
+}
+
+/// Interceptor adding headers to every request before it is signed,
+/// e.g. x-amz-expected-bucket-owner, audit headers or tenant ids
+#[derive(Debug, Clone, Default)]
+pub struct HeaderInterceptor {
+    headers: Vec<(String, String)>,
+}
+
+impl HeaderInterceptor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add header, existing header with the same name is replaced
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Fail requests to buckets not owned by account_id
+    pub fn expected_bucket_owner(self, account_id: impl Into<String>) -> Self {
+        self.header("x-amz-expected-bucket-owner", account_id)
+    }
+
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+}
+
+impl Intercept for HeaderInterceptor {
+    fn name(&self) -> &'static str {
+        "HeaderInterceptor"
+    }
+
+    fn modify_before_signing(
+        &self,
+        context: &mut BeforeTransmitInterceptorContextMut<'_>,
+        _runtime_components: &RuntimeComponents,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let headers = context.request_mut().headers_mut();
+        for (name, value) in &self.headers {
+            headers.try_insert(name.clone(), value.clone())?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+use async_compression::tokio::bufread::{GzipDecoder, GzipEncoder, ZstdDecoder, ZstdEncoder};
+use tokio::io::{AsyncBufRead, AsyncRead};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression of uploaded and downloaded data, set once on TransferManager
+/// to compress uploads and decompress downloads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Codec {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Codec {
+    /// Get Content-Encoding of compressed objects
+    pub fn content_encoding(&self) -> Option<&'static str> {
+        match self {
+            Codec::None => None,
+            Codec::Gzip => Some("gzip"),
+            Codec::Zstd => Some("zstd"),
+        }
+    }
+
+    /// Get key extension of compressed objects
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Codec::None => None,
+            Codec::Gzip => Some("gz"),
+            Codec::Zstd => Some("zst"),
+        }
+    }
+
+    /// Get codec of Content-Encoding, None if encoding is not supported
+    pub fn from_content_encoding(encoding: &str) -> Option<Self> {
+        match encoding.trim().to_ascii_lowercase().as_str() {
+            "identity" | "" => Some(Codec::None),
+            "gzip" | "x-gzip" => Some(Codec::Gzip),
+            "zstd" => Some(Codec::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Detect codec by magic bytes at the start of data
+    pub fn from_magic(data: &[u8]) -> Self {
+        if data.starts_with(GZIP_MAGIC) {
+            Codec::Gzip
+        } else if data.starts_with(ZSTD_MAGIC) {
+            Codec::Zstd
+        } else {
+            Codec::None
+        }
+    }
+
+    /// Wrap reader to read compressed data
+    pub fn encoder<'a, R: AsyncBufRead + Unpin + Send + 'a>(&self, reader: R) -> Box<dyn AsyncRead + Unpin + Send + 'a> {
+        match self {
+            Codec::None => Box::new(reader),
+            Codec::Gzip => Box::new(GzipEncoder::new(reader)),
+            Codec::Zstd => Box::new(ZstdEncoder::new(reader)),
+        }
+    }
+
+    /// Wrap reader of compressed data to read decompressed data
+    pub fn decoder<'a, R: AsyncBufRead + Unpin + Send + 'a>(&self, reader: R) -> Box<dyn AsyncRead + Unpin + Send + 'a> {
+        match self {
+            Codec::None => Box::new(reader),
+            Codec::Gzip => {
+                let mut decoder = GzipDecoder::new(reader);
+                decoder.multiple_members(true);
+                Box::new(decoder)
+            }
+            Codec::Zstd => Box::new(ZstdDecoder::new(reader)),
+        }
+    }
+}
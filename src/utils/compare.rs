@@ -0,0 +1,64 @@
+use std::path::Path;
+
+use aws_sdk_s3::Client;
+use tokio::fs;
+
+use crate::utils::{compute_s3_etag, head_object, parse_etag, EtagKind, S3Key};
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+/// Result of comparing local file with AWS S3 object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Identical,
+    DiffersBySize,
+    DiffersByContent,
+    MissingRemote,
+}
+
+/// Compare local file with AWS S3 object by size and ETag,
+/// multipart ETags are recomputed locally with the part size of the remote object.
+/// ETags of objects encrypted with SSE-KMS or SSE-C are not MD5 based and always differ by content
+pub async fn compare_with_remote(client: Client, bucket: &str, key: impl Into<S3Key>, local_path: impl AsRef<Path>) -> Result<Comparison, UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let local_path = local_path.as_ref();
+    let Some(remote) = head_object(client.clone(), bucket, key).await? else {
+        return Ok(Comparison::MissingRemote);
+    };
+
+    let file_size = fs::metadata(local_path).await?.len();
+    let remote_size = remote.content_length().unwrap_or(0) as u64;
+    if file_size != remote_size {
+        return Ok(Comparison::DiffersBySize);
+    }
+
+    if etag_matches(client, bucket, key, remote.e_tag().unwrap_or_default(), local_path).await? {
+        Ok(Comparison::Identical)
+    } else {
+        Ok(Comparison::DiffersByContent)
+    }
+}
+
+/// Check if ETag of local file matches ETag of AWS S3 object, for multipart ETags
+/// part size is taken from the first part of the remote object
+pub(crate) async fn etag_matches(client: Client, bucket: &str, key: &str, e_tag: &str, local_path: &Path) -> Result<bool, UtilsError> {
+    let part_size = match parse_etag(e_tag) {
+        EtagKind::SinglePart { .. } => None,
+        EtagKind::Multipart { .. } => {
+            let first_part = client
+                .head_object()
+                .bucket(bucket)
+                .key(key)
+                .part_number(1)
+                .send()
+                .await
+                .with_context(|| ErrorContext::object("HeadObject", bucket, key))?;
+            first_part.content_length().map(|val| val as u64)
+        }
+        EtagKind::Unknown(_) => return Ok(false),
+    };
+
+    let local_etag = compute_s3_etag(local_path, part_size).await?;
+
+    Ok(parse_etag(&local_etag) == parse_etag(e_tag))
+}
@@ -1,3 +1,14 @@
 pub const AWS_MAX_RETRIES: u32 = 10;
 pub const CHUNK_SIZE: u64 = 10_000_000; // 10 MiB
-pub const MAX_CHUNKS: u64 = 10_000; // 10 GiB 
\ No newline at end of file
+pub const MAX_CHUNKS: u64 = 10_000; // 10 GiB 
+pub const MAX_COPY_SIZE: u64 = 5 * 1024 * 1024 * 1024; // 5 GiB
+pub const COPY_PART_SIZE: u64 = 256 * 1024 * 1024; // 256 MiB
+pub const READ_BUF_SIZE: usize = 1024 * 1024; // 1 MiB
+pub const MAX_CONCURRENCY: usize = 16;
+pub const MAX_DELETE_KEYS: usize = 1_000;
+pub const TRASH_PREFIX: &str = "_trash";
+pub const EXPIRATION_TAG: &str = "expire-after";
+pub const EXPIRATION_DAYS: [u32; 6] = [1, 7, 30, 90, 180, 365];
+pub const MAX_KEY_LEN: usize = 1024;
+pub const MIN_PART_SIZE: usize = 5 * 1024 * 1024; // 5 MiB
+pub const SIDECAR_EXTENSION: &str = "sha256";
\ No newline at end of file
@@ -0,0 +1,30 @@
+use aws_sdk_s3::Client;
+use csv_async::AsyncReaderBuilder;
+use futures_util::{Stream, StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+
+use crate::utils::{get_aws_object_with_options, GetOptions};
+use crate::error::UtilsError;
+
+/// Read CSV object with header row into records
+pub async fn read_csv<T: DeserializeOwned + 'static>(client: Client, bucket: &str, key: &str) -> Result<Vec<T>, UtilsError> {
+    read_csv_stream(client, bucket, key).await?.try_collect().await
+}
+
+/// Stream records of CSV object with header row, records are deserialized while body is downloaded
+pub async fn read_csv_stream<T: DeserializeOwned + 'static>(
+    client: Client,
+    bucket: &str,
+    key: &str,
+) -> Result<impl Stream<Item = Result<T, UtilsError>>, UtilsError> {
+    let res = get_aws_object_with_options(client, bucket, key, &GetOptions::default()).await?;
+    let reader = res.body.into_async_read();
+
+    let records = AsyncReaderBuilder::new()
+        .has_headers(true)
+        .create_deserializer(reader)
+        .into_deserialize::<T>()
+        .map(|record| record.map_err(UtilsError::from));
+
+    Ok(records)
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use aws_sdk_s3::Client;
+use datafusion::{
+    datasource::{
+        file_format::{csv::CsvFormat, json::JsonFormat, parquet::ParquetFormat, FileFormat},
+        listing::{ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl},
+    },
+    prelude::SessionContext,
+};
+use url::Url;
+
+use crate::utils::{S3ObjectStore, S3Path};
+use crate::error::UtilsError;
+
+/// Format of files of listing table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TableFormat {
+    #[default]
+    Parquet,
+    /// CSV with header row
+    Csv,
+    /// JSON lines
+    Json,
+}
+
+impl TableFormat {
+    fn file_format(&self) -> Arc<dyn FileFormat> {
+        match self {
+            TableFormat::Parquet => Arc::new(ParquetFormat::default()),
+            TableFormat::Csv => Arc::new(CsvFormat::default().with_has_header(true)),
+            TableFormat::Json => Arc::new(JsonFormat::default()),
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            TableFormat::Parquet => ".parquet",
+            TableFormat::Csv => ".csv",
+            TableFormat::Json => ".json",
+        }
+    }
+}
+
+/// Register all files of format under prefix as table, requests of queries are made by client
+/// through S3ObjectStore registered for bucket, so its credentials and endpoint are used
+pub async fn register_listing_table(
+    ctx: &SessionContext,
+    client: Client,
+    table_name: &str,
+    s3path: &S3Path,
+    format: TableFormat,
+) -> Result<(), UtilsError> {
+    let bucket_url = Url::parse(&format!("s3://{}", s3path.bucket)).map_err(|e| UtilsError::UnexpectedError(e.into()))?;
+    ctx.register_object_store(&bucket_url, Arc::new(S3ObjectStore::new(client, &s3path.bucket)));
+
+    let prefix = match s3path.key.is_empty() || s3path.key.ends_with('/') {
+        true => s3path.key.clone(),
+        false => format!("{}/", s3path.key),
+    };
+    let table_url = ListingTableUrl::parse(format!("s3://{}/{}", s3path.bucket, prefix))?;
+    let options = ListingOptions::new(format.file_format()).with_file_extension(format.extension());
+    let schema = options.infer_schema(&ctx.state(), &table_url).await?;
+    let config = ListingTableConfig::new(table_url)
+        .with_listing_options(options)
+        .with_schema(schema);
+    ctx.register_table(table_name, Arc::new(ListingTable::try_new(config)?))?;
+
+    Ok(())
+}
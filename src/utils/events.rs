@@ -0,0 +1,132 @@
+use std::future::Future;
+
+use aws_sdk_sqs::Client as SqsClient;
+use color_eyre::eyre::eyre;
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+use crate::utils::{CancellationToken, S3Path};
+use crate::error::UtilsError;
+
+const WAIT_TIME_SECS: i32 = 20;
+const MAX_MESSAGES: i32 = 10;
+
+/// S3 event notification, test event sent on configuration has no records
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct S3EventNotification {
+    #[serde(rename = "Records", default)]
+    pub records: Vec<S3EventRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct S3EventRecord {
+    /// Event type without s3: prefix, e.g. ObjectCreated:Put
+    pub event_name: String,
+    pub event_time: Option<String>,
+    pub aws_region: Option<String>,
+    pub s3: S3Entity,
+}
+
+impl S3EventRecord {
+    /// Get path of object, key of notification is url encoded with + for space
+    pub fn s3_path(&self) -> Result<S3Path, UtilsError> {
+        let key = self.s3.object.key.replace('+', " ");
+        let key = urlencoding::decode(&key).map_err(|e| UtilsError::UnexpectedError(e.into()))?;
+        Ok(S3Path::new(self.s3.bucket.name.clone(), key.into_owned()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct S3Entity {
+    pub bucket: S3EventBucket,
+    pub object: S3EventObject,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct S3EventBucket {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct S3EventObject {
+    pub key: String,
+    pub size: Option<u64>,
+    pub e_tag: Option<String>,
+    pub version_id: Option<String>,
+    pub sequencer: Option<String>,
+}
+
+/// SNS envelope of notification delivered to SQS via SNS topic
+#[derive(Deserialize)]
+struct SnsEnvelope {
+    #[serde(rename = "Message")]
+    message: String,
+}
+
+/// Parse SQS message body with S3 event notification sent directly or via SNS
+pub fn parse_event_message(body: &str) -> Result<S3EventNotification, UtilsError> {
+    if let Ok(envelope) = serde_json::from_str::<SnsEnvelope>(body) {
+        return Ok(serde_json::from_str(&envelope.message)?);
+    }
+
+    Ok(serde_json::from_str(body)?)
+}
+
+/// Receive S3 event notifications from SQS queue calling handler with path and event type of every record
+/// until token is cancelled, message is deleted after all its records are handled, messages with failed
+/// records are left in queue to be received again after visibility timeout
+pub async fn run_event_loop<F, Fut>(client: SqsClient, queue_url: &str, token: &CancellationToken, mut handler: F) -> Result<(), UtilsError>
+where
+    F: FnMut(S3Path, String) -> Fut,
+    Fut: Future<Output = Result<(), UtilsError>>,
+{
+    loop {
+        let req = client
+            .receive_message()
+            .queue_url(queue_url)
+            .wait_time_seconds(WAIT_TIME_SECS)
+            .max_number_of_messages(MAX_MESSAGES)
+            .send();
+        let res = match token.run_until_cancelled(req).await {
+            Some(res) => res?,
+            None => return Ok(()),
+        };
+
+        for message in res.messages() {
+            let notification = match parse_event_message(message.body().unwrap_or_default()) {
+                Ok(notification) => notification,
+                Err(err) => {
+                    warn!(queue_url, message_id = message.message_id(), error = %err, "failed to parse event message");
+                    continue;
+                }
+            };
+            let mut handled = true;
+            for record in &notification.records {
+                let res = match record.s3_path() {
+                    Ok(s3path) => handler(s3path, record.event_name.clone()).await,
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = res {
+                    warn!(queue_url, event = %record.event_name, key = %record.s3.object.key, error = %err, "failed to handle event");
+                    handled = false;
+                }
+            }
+            if !handled {
+                continue;
+            }
+
+            let receipt_handle = message
+                .receipt_handle()
+                .ok_or_else(|| UtilsError::UnexpectedError(eyre!("Missing receipt handle of message in: {}", queue_url)))?;
+            client
+                .delete_message()
+                .queue_url(queue_url)
+                .receipt_handle(receipt_handle)
+                .send()
+                .await?;
+            debug!(queue_url, message_id = message.message_id(), "handled event message");
+        }
+    }
+}
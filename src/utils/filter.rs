@@ -0,0 +1,100 @@
+/// Include or exclude rule with glob pattern, * matches any characters including / and ? matches one character
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterRule {
+    Include(String),
+    Exclude(String),
+}
+
+/// Ordered include/exclude rules applied to paths relative to source directory or prefix,
+/// all paths are included by default and later rules take precedence like in AWS CLI
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filters {
+    pub rules: Vec<FilterRule>,
+}
+
+impl Filters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.rules.push(FilterRule::Include(pattern.into()));
+        self
+    }
+
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.rules.push(FilterRule::Exclude(pattern.into()));
+        self
+    }
+
+    /// Check if relative path passes filters
+    pub fn is_included(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find_map(|rule| match rule {
+                FilterRule::Include(pattern) if glob_match(pattern, path) => Some(true),
+                FilterRule::Exclude(pattern) if glob_match(pattern, path) => Some(false),
+                _ => None,
+            })
+            .unwrap_or(true)
+    }
+}
+
+/// Match text against glob pattern with * and ? wildcards
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_match_wildcards() {
+        assert!(glob_match("*.csv", "data.csv"));
+        assert!(glob_match("*.csv", "dir/data.csv"));
+        assert!(!glob_match("*.csv", "data.csv.gz"));
+        assert!(glob_match("dir/?.txt", "dir/a.txt"));
+        assert!(!glob_match("dir/?.txt", "dir/ab.txt"));
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(!glob_match("a*b*c", "axxbyy"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("**", "any/path"));
+        assert!(!glob_match("", "a"));
+    }
+
+    #[test]
+    fn later_rules_take_precedence() {
+        let filters = Filters::new().exclude("*").include("*.csv").exclude("tmp/*");
+        assert!(filters.is_included("data.csv"));
+        assert!(!filters.is_included("data.json"));
+        assert!(!filters.is_included("tmp/data.csv"));
+        assert!(Filters::new().is_included("anything"));
+    }
+}
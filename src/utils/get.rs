@@ -0,0 +1,134 @@
+use std::{ops::Range, path::Path};
+
+use aws_sdk_s3::{operation::{get_object::{GetObjectError, GetObjectOutput}, head_object::{HeadObjectError, HeadObjectOutput}}, Client};
+use tokio::{fs::File, io::{AsyncWriteExt, BufWriter}};
+
+use crate::utils::{GetOptions, S3Key};
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+/// Get AWS GetObjectOutput
+#[deprecated(since = "0.1.0", note = "use get_aws_object_with_options")]
+pub async fn get_aws_object(client: Client, bucket: &str, key: &str) -> Result<GetObjectOutput, UtilsError> {
+    get_aws_object_with_options(client, bucket, key, &GetOptions::default()).await
+}
+
+/// Get AWS GetObjectOutput with version, range and If-Match of options
+pub async fn get_aws_object_with_options(client: Client, bucket: &str, key: impl Into<S3Key>, opts: &GetOptions) -> Result<GetObjectOutput, UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let req = client
+        .get_object()
+        .bucket(bucket)
+        .key(key)
+        .set_version_id(opts.version_id.clone())
+        .set_range(opts.range.map(|(first, last)| format!("bytes={}-{}", first, last)))
+        .set_if_match(opts.if_match.clone());
+
+    let res = req
+        .send()
+        .await
+        .map_err(|err| UtilsError::from(err).or_bucket_not_found(bucket))
+        .with_context(|| ErrorContext::object("GetObject", bucket, key))?;
+
+    Ok(res)
+}
+
+/// Get None if key doesn't exist in AWS S3, BucketNotFound error if bucket doesn't exist
+pub async fn try_get_file(client: Client, bucket: &str, key: impl Into<S3Key>) -> Result<Option<GetObjectOutput>, UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let resp = client
+        .get_object()
+        .bucket(bucket)
+        .key(key);
+
+    let res = resp.send().await;
+
+    match res {
+        Ok(res) => Ok(Some(res)),
+        Err(sdk_err) => match sdk_err.as_service_error() {
+            Some(GetObjectError::NoSuchKey(_)) => Ok(None),
+            _ => Err(UtilsError::from(sdk_err).or_bucket_not_found(bucket).context(ErrorContext::object("GetObject", bucket, key))),
+        }
+    }
+} 
+
+/// Get AWS HeadObjectOutput, None if key doesn't exist in AWS S3,
+/// also None for missing bucket as HEAD response has no error code
+pub async fn head_object(client: Client, bucket: &str, key: impl Into<S3Key>) -> Result<Option<HeadObjectOutput>, UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let res = client
+        .head_object()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await;
+
+    match res {
+        Ok(res) => Ok(Some(res)),
+        Err(sdk_err) => match sdk_err.as_service_error() {
+            Some(HeadObjectError::NotFound(_)) => Ok(None),
+            _ => Err(UtilsError::from(sdk_err).context(ErrorContext::object("HeadObject", bucket, key))),
+        }
+    }
+}
+
+/// Check if key exists in AWS S3
+pub async fn object_exists(client: Client, bucket: &str, key: impl Into<S3Key>) -> Result<bool, UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let res = head_object(client, bucket, key).await?;
+
+    Ok(res.is_some())
+}
+
+/// Read file from AWS S3 
+pub async fn read_file(client: Client, bucket: &str, key: impl Into<S3Key>) -> Result<Vec<u8>, UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let mut buf = Vec::new();
+    let mut object = get_aws_object_with_options(client, bucket, key, &GetOptions::default()).await?;
+    while let Some(bytes) = object.body.try_next().await.with_context(|| ErrorContext::object("GetObject", bucket, key))? {
+        buf.extend(bytes.to_vec());
+    }
+
+    Ok(buf)
+}
+
+/// Read byte range of object, end is exclusive
+pub async fn read_range(client: Client, bucket: &str, key: impl Into<S3Key>, range: Range<u64>) -> Result<Vec<u8>, UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    if range.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut buf = Vec::with_capacity((range.end - range.start) as usize);
+    let opts = GetOptions {
+        range: Some((range.start, range.end - 1)),
+        ..Default::default()
+    };
+    let mut object = get_aws_object_with_options(client, bucket, key, &opts).await?;
+    while let Some(bytes) = object.body.try_next().await.with_context(|| ErrorContext::object("GetObject", bucket, key))? {
+        buf.extend_from_slice(&bytes);
+    }
+
+    Ok(buf)
+}
+
+pub async fn download_file(client: Client, bucket: &str, key: impl Into<S3Key>, file_path: impl AsRef<Path>) -> Result<(), UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let res = get_aws_object_with_options(client.clone(), bucket, key, &GetOptions::default()).await?;
+    
+    let mut data = res.body;
+    let file = File::create(file_path.as_ref()).await?;
+    let mut buf_writer = BufWriter::new(file);
+    while let Some(bytes) = data.try_next().await.with_context(|| ErrorContext::object("GetObject", bucket, key))? {
+        buf_writer.write_all(&bytes).await?;
+    }
+    buf_writer.flush().await?;
+
+    Ok(())
+} 
@@ -0,0 +1,146 @@
+use aws_sdk_s3::{
+    types::{InventoryConfiguration, InventoryDestination, InventoryFilter, InventoryFormat, InventoryFrequency, InventoryIncludedObjectVersions, InventoryOptionalField, InventoryS3BucketDestination, InventorySchedule},
+    Client,
+};
+
+use crate::utils::S3Path;
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+/// S3 Inventory report file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InventoryReportFormat {
+    Csv,
+    Orc,
+    Parquet,
+}
+
+/// S3 Inventory report schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InventoryReportFrequency {
+    Daily,
+    Weekly,
+}
+
+/// S3 Inventory configuration publishing reports to destination bucket and prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InventoryConfig {
+    pub id: String,
+    pub destination: S3Path,
+    pub destination_account_id: Option<String>,
+    pub format: InventoryReportFormat,
+    pub frequency: InventoryReportFrequency,
+    /// Optional fields like Size, LastModifiedDate, ETag, StorageClass
+    pub fields: Vec<String>,
+    pub prefix: Option<String>,
+    pub include_versions: bool,
+    pub enabled: bool,
+}
+
+impl InventoryConfig {
+    pub fn new(id: impl Into<String>, destination: S3Path) -> Self {
+        Self {
+            id: id.into(),
+            destination,
+            destination_account_id: None,
+            format: InventoryReportFormat::Csv,
+            frequency: InventoryReportFrequency::Daily,
+            fields: Vec::new(),
+            prefix: None,
+            include_versions: false,
+            enabled: true,
+        }
+    }
+
+    pub fn destination_account_id(mut self, account_id: impl Into<String>) -> Self {
+        self.destination_account_id = Some(account_id.into());
+        self
+    }
+
+    pub fn format(mut self, format: InventoryReportFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn frequency(mut self, frequency: InventoryReportFrequency) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    pub fn field(mut self, field: impl Into<String>) -> Self {
+        self.fields.push(field.into());
+        self
+    }
+
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    pub fn include_versions(mut self, include_versions: bool) -> Self {
+        self.include_versions = include_versions;
+        self
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    fn to_sdk(&self) -> Result<InventoryConfiguration, UtilsError> {
+        let format = match self.format {
+            InventoryReportFormat::Csv => InventoryFormat::Csv,
+            InventoryReportFormat::Orc => InventoryFormat::Orc,
+            InventoryReportFormat::Parquet => InventoryFormat::Parquet,
+        };
+        let frequency = match self.frequency {
+            InventoryReportFrequency::Daily => InventoryFrequency::Daily,
+            InventoryReportFrequency::Weekly => InventoryFrequency::Weekly,
+        };
+        let included_object_versions = if self.include_versions {
+            InventoryIncludedObjectVersions::All
+        } else {
+            InventoryIncludedObjectVersions::Current
+        };
+        let prefix = Some(self.destination.key.clone()).filter(|prefix| !prefix.is_empty());
+        let s3_bucket_destination = InventoryS3BucketDestination::builder()
+            .bucket(format!("arn:aws:s3:::{}", self.destination.bucket))
+            .set_account_id(self.destination_account_id.clone())
+            .set_prefix(prefix)
+            .format(format)
+            .build()?;
+        let filter = match &self.prefix {
+            Some(prefix) => Some(InventoryFilter::builder().prefix(prefix).build()?),
+            None => None,
+        };
+        let fields = self.fields
+            .iter()
+            .map(|field| InventoryOptionalField::from(field.as_str()))
+            .collect();
+
+        let configuration = InventoryConfiguration::builder()
+            .id(&self.id)
+            .destination(InventoryDestination::builder().s3_bucket_destination(s3_bucket_destination).build())
+            .schedule(InventorySchedule::builder().frequency(frequency).build()?)
+            .included_object_versions(included_object_versions)
+            .set_optional_fields(Some(fields))
+            .set_filter(filter)
+            .is_enabled(self.enabled)
+            .build()?;
+
+        Ok(configuration)
+    }
+}
+
+/// Create or replace bucket inventory configuration
+pub async fn put_bucket_inventory_configuration(client: Client, bucket: &str, config: &InventoryConfig) -> Result<(), UtilsError> {
+    client
+        .put_bucket_inventory_configuration()
+        .bucket(bucket)
+        .id(&config.id)
+        .inventory_configuration(config.to_sdk()?)
+        .send()
+        .await
+        .with_context(|| ErrorContext::new("PutBucketInventoryConfiguration").bucket(bucket))?;
+
+    Ok(())
+}
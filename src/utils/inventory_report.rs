@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "parquet")]
+use arrow_array::{cast::AsArray, types::{Int64Type, TimestampMillisecondType}, Array, RecordBatch};
+use aws_sdk_s3::Client;
+#[cfg(feature = "parquet")]
+use aws_smithy_types::date_time::{DateTime, Format};
+use color_eyre::eyre::eyre;
+use csv_async::{AsyncReaderBuilder, StringRecord};
+use futures_util::{stream::{self, BoxStream}, StreamExt, TryStreamExt};
+use serde::Deserialize;
+use tokio::io::BufReader;
+
+#[cfg(feature = "parquet")]
+use crate::utils::read_parquet;
+use crate::utils::{get_aws_object_with_options, read_file, Codec, GetOptions, S3Path};
+use crate::error::UtilsError;
+
+/// S3 Inventory manifest.json listing files of one report
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryManifest {
+    pub source_bucket: String,
+    /// ARN of destination bucket
+    pub destination_bucket: String,
+    /// CSV, ORC or Parquet
+    pub file_format: String,
+    /// Comma separated field names of CSV, message schema of Parquet and ORC
+    pub file_schema: String,
+    pub files: Vec<InventoryManifestFile>,
+}
+
+impl InventoryManifest {
+    /// Get name of bucket with report files
+    pub fn destination_bucket_name(&self) -> &str {
+        self.destination_bucket.trim_start_matches("arn:aws:s3:::")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct InventoryManifestFile {
+    pub key: String,
+    pub size: u64,
+    #[serde(rename = "MD5checksum")]
+    pub md5_checksum: Option<String>,
+}
+
+/// Object listed by S3 Inventory report, fields not included in report are None
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InventoryRecord {
+    pub bucket: String,
+    pub key: String,
+    pub version_id: Option<String>,
+    pub is_latest: Option<bool>,
+    pub is_delete_marker: Option<bool>,
+    pub size: Option<u64>,
+    /// ISO 8601 date time
+    pub last_modified: Option<String>,
+    pub e_tag: Option<String>,
+    pub storage_class: Option<String>,
+}
+
+/// Read manifest.json of S3 Inventory report
+pub async fn read_inventory_manifest(client: Client, manifest: &S3Path) -> Result<InventoryManifest, UtilsError> {
+    let data = read_file(client, &manifest.bucket, &manifest.key).await?;
+
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// Stream records of all files of S3 Inventory report one file at a time,
+/// CSV files are streamed while downloaded, Parquet files need parquet feature
+pub fn inventory_records_stream(client: Client, manifest: &InventoryManifest) -> BoxStream<'static, Result<InventoryRecord, UtilsError>> {
+    let bucket = manifest.destination_bucket_name().to_string();
+    let format = manifest.file_format.to_ascii_lowercase();
+    let schema: Vec<String> = manifest.file_schema.split(',').map(|name| name.trim().to_string()).collect();
+    let files: Vec<S3Path> = manifest.files.iter().map(|file| S3Path::new(bucket.clone(), file.key.clone())).collect();
+
+    stream::iter(files)
+        .then(move |file| {
+            let client = client.clone();
+            let format = format.clone();
+            let schema = schema.clone();
+            async move {
+                match format.as_str() {
+                    "csv" => read_csv_file(client, file, schema).await,
+                    #[cfg(feature = "parquet")]
+                    "parquet" => read_parquet_file(client, file).await,
+                    _ => Err(UtilsError::UnexpectedError(eyre!("Unsupported inventory file format: {}", format))),
+                }
+            }
+        })
+        .try_flatten()
+        .boxed()
+}
+
+async fn read_csv_file(client: Client, file: S3Path, schema: Vec<String>) -> Result<BoxStream<'static, Result<InventoryRecord, UtilsError>>, UtilsError> {
+    let res = get_aws_object_with_options(client, &file.bucket, &file.key, &GetOptions::default()).await?;
+    let codec = match file.key.ends_with(".gz") {
+        true => Codec::Gzip,
+        false => Codec::None,
+    };
+    let reader = codec.decoder(BufReader::new(res.body.into_async_read()));
+    let columns: HashMap<String, usize> = schema.into_iter().enumerate().map(|(index, name)| (name, index)).collect();
+
+    let records = AsyncReaderBuilder::new()
+        .has_headers(false)
+        .create_reader(reader)
+        .into_records()
+        .map(move |row| csv_record(&columns, &row?));
+
+    Ok(records.boxed())
+}
+
+fn csv_record(columns: &HashMap<String, usize>, row: &StringRecord) -> Result<InventoryRecord, UtilsError> {
+    let field = |name: &str| {
+        columns
+            .get(name)
+            .and_then(|index| row.get(*index))
+            .filter(|val| !val.is_empty())
+            .map(|val| val.to_string())
+    };
+    let key = field("Key").unwrap_or_default();
+    let key = urlencoding::decode(&key).map_err(|e| UtilsError::UnexpectedError(e.into()))?.into_owned();
+
+    Ok(InventoryRecord {
+        bucket: field("Bucket").unwrap_or_default(),
+        key,
+        version_id: field("VersionId"),
+        is_latest: field("IsLatest").map(|val| val == "true"),
+        is_delete_marker: field("IsDeleteMarker").map(|val| val == "true"),
+        size: field("Size").and_then(|val| val.parse().ok()),
+        last_modified: field("LastModifiedDate"),
+        e_tag: field("ETag"),
+        storage_class: field("StorageClass"),
+    })
+}
+
+#[cfg(feature = "parquet")]
+async fn read_parquet_file(client: Client, file: S3Path) -> Result<BoxStream<'static, Result<InventoryRecord, UtilsError>>, UtilsError> {
+    let batches = read_parquet(client, &file).await?;
+    let mut records = Vec::new();
+    for batch in &batches {
+        let string = |name: &str, row: usize| {
+            column(batch, name)
+                .and_then(|column| column.as_string_opt::<i32>())
+                .filter(|column| column.is_valid(row))
+                .map(|column| column.value(row).to_string())
+        };
+        let boolean = |name: &str, row: usize| {
+            column(batch, name)
+                .and_then(|column| column.as_boolean_opt())
+                .filter(|column| column.is_valid(row))
+                .map(|column| column.value(row))
+        };
+        for row in 0..batch.num_rows() {
+            records.push(Ok(InventoryRecord {
+                bucket: string("bucket", row).unwrap_or_default(),
+                key: string("key", row).unwrap_or_default(),
+                version_id: string("version_id", row),
+                is_latest: boolean("is_latest", row),
+                is_delete_marker: boolean("is_delete_marker", row),
+                size: column(batch, "size")
+                    .and_then(|column| column.as_primitive_opt::<Int64Type>())
+                    .filter(|column| column.is_valid(row))
+                    .map(|column| column.value(row) as u64),
+                last_modified: column(batch, "last_modified_date")
+                    .and_then(|column| column.as_primitive_opt::<TimestampMillisecondType>())
+                    .filter(|column| column.is_valid(row))
+                    .and_then(|column| DateTime::from_millis(column.value(row)).fmt(Format::DateTime).ok()),
+                e_tag: string("e_tag", row),
+                storage_class: string("storage_class", row),
+            }));
+        }
+    }
+
+    Ok(stream::iter(records).boxed())
+}
+
+#[cfg(feature = "parquet")]
+fn column<'a>(batch: &'a RecordBatch, name: &str) -> Option<&'a dyn Array> {
+    batch.column_by_name(name).map(|column| column.as_ref())
+}
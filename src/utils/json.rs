@@ -0,0 +1,74 @@
+use aws_sdk_s3::{primitives::ByteStream, Client};
+use futures_util::{stream, Stream};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::utils::{get_aws_object_with_options, read_file, GetOptions, S3Writer};
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+const JSON_CONTENT_TYPE: &str = "application/json";
+const JSONL_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Read JSON object
+pub async fn read_json<T: DeserializeOwned>(client: Client, bucket: &str, key: &str) -> Result<T, UtilsError> {
+    let data = read_file(client, bucket, key).await?;
+
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// Write value as JSON object
+pub async fn write_json<T: Serialize>(client: Client, bucket: &str, key: &str, value: &T) -> Result<(), UtilsError> {
+    let data = serde_json::to_vec(value)?;
+
+    client
+        .put_object()
+        .bucket(bucket)
+        .key(key)
+        .content_type(JSON_CONTENT_TYPE)
+        .body(ByteStream::from(data))
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("PutObject", bucket, key))?;
+
+    Ok(())
+}
+
+/// Stream records of JSON lines object, empty lines are skipped
+pub async fn read_jsonl_stream<T: DeserializeOwned>(
+    client: Client,
+    bucket: &str,
+    key: &str,
+) -> Result<impl Stream<Item = Result<T, UtilsError>>, UtilsError> {
+    let res = get_aws_object_with_options(client, bucket, key, &GetOptions::default()).await?;
+    let lines = BufReader::new(res.body.into_async_read()).lines();
+
+    Ok(stream::unfold(Some(lines), |lines| async move {
+        let mut lines = lines?;
+        loop {
+            match lines.next_line().await {
+                Ok(Some(line)) if line.trim().is_empty() => continue,
+                Ok(Some(line)) => return Some((serde_json::from_str(&line).map_err(UtilsError::from), Some(lines))),
+                Ok(None) => return None,
+                Err(err) => return Some((Err(err.into()), None)),
+            }
+        }
+    }))
+}
+
+/// Write records as JSON lines object by multipart upload, returns number of written bytes
+pub async fn write_jsonl_from_iter<T, I>(client: Client, bucket: &str, key: &str, records: I) -> Result<u64, UtilsError>
+where
+    T: Serialize,
+    I: IntoIterator<Item = T>,
+{
+    let mut writer = S3Writer::new(client, bucket, key).content_type(JSONL_CONTENT_TYPE);
+    let mut line = Vec::new();
+    for record in records {
+        line.clear();
+        serde_json::to_writer(&mut line, &record)?;
+        line.push(b'\n');
+        writer.write(&line).await?;
+    }
+
+    Ok(writer.finish().await?.bytes)
+}
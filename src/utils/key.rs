@@ -0,0 +1,129 @@
+use std::fmt;
+
+
+use crate::utils::MAX_KEY_LEN;
+use crate::error::UtilsError;
+
+/// AWS S3 object key, accidental leading slashes are stripped on conversion.
+/// Functions on a single object take impl Into<S3Key>, functions on prefixes and batches
+/// of keys (listing, delete_keys, head_objects, presign_get_many) take &str and &[String]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct S3Key(String);
+
+impl S3Key {
+    /// Get validated key, functions taking impl Into<S3Key> validate it the same way
+    pub fn new(key: impl Into<S3Key>) -> Result<Self, UtilsError> {
+        let key = key.into();
+        key.validate()?;
+
+        Ok(key)
+    }
+
+    /// Check key is 1 to MAX_KEY_LEN bytes long without control characters
+    pub fn validate(&self) -> Result<(), UtilsError> {
+        if self.0.is_empty() {
+            return Err(UtilsError::InvalidKey("empty key".to_string()));
+        }
+        if self.0.len() > MAX_KEY_LEN {
+            let err = format!("longer than {} bytes: {}...", MAX_KEY_LEN, self.0.chars().take(64).collect::<String>());
+            return Err(UtilsError::InvalidKey(err));
+        }
+        if self.0.chars().any(char::is_control) {
+            return Err(UtilsError::InvalidKey(format!("control character in {:?}", self.0)));
+        }
+
+        Ok(())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Get key with name appended after /
+    pub fn join(&self, name: &str) -> Self {
+        let name = name.trim_start_matches('/');
+        if self.0.is_empty() || self.0.ends_with('/') {
+            Self(format!("{}{}", self.0, name))
+        } else {
+            Self(format!("{}/{}", self.0, name))
+        }
+    }
+
+    /// Get last segment of key, empty for prefix ending with /
+    pub fn file_name(&self) -> &str {
+        self.0.rsplit('/').next().unwrap_or_default()
+    }
+}
+
+impl From<String> for S3Key {
+    fn from(key: String) -> Self {
+        if key.starts_with('/') {
+            Self::from(key.as_str())
+        } else {
+            Self(key)
+        }
+    }
+}
+
+impl From<&String> for S3Key {
+    fn from(key: &String) -> Self {
+        Self::from(key.as_str())
+    }
+}
+
+impl From<&str> for S3Key {
+    fn from(key: &str) -> Self {
+        Self(key.trim_start_matches('/').to_string())
+    }
+}
+
+impl From<S3Key> for String {
+    fn from(key: S3Key) -> Self {
+        key.0
+    }
+}
+
+impl AsRef<str> for S3Key {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for S3Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions_strip_leading_slashes_like_new() {
+        let owned = String::from("//dir/file.csv");
+        assert_eq!(S3Key::from(owned.as_str()).as_str(), "dir/file.csv");
+        assert_eq!(S3Key::from(&owned), S3Key::from(owned.clone()));
+        assert_eq!(S3Key::new(owned).unwrap().as_str(), "dir/file.csv");
+        assert_eq!(S3Key::from("dir/").as_str(), "dir/");
+    }
+
+    #[test]
+    fn new_rejects_invalid_keys() {
+        assert!(matches!(S3Key::new(""), Err(UtilsError::InvalidKey(_))));
+        assert!(matches!(S3Key::new("/"), Err(UtilsError::InvalidKey(_))));
+        assert!(matches!(S3Key::new("dir/\nfile"), Err(UtilsError::InvalidKey(_))));
+        assert!(matches!(S3Key::new("a".repeat(MAX_KEY_LEN + 1)), Err(UtilsError::InvalidKey(_))));
+        assert!(S3Key::new("a".repeat(MAX_KEY_LEN)).is_ok());
+        assert!(S3Key::new("dir/файл name.csv").is_ok());
+    }
+
+    #[test]
+    fn join_and_file_name() {
+        assert_eq!(S3Key::from("dir").join("/file.csv").as_str(), "dir/file.csv");
+        assert_eq!(S3Key::from("dir/").join("file.csv").as_str(), "dir/file.csv");
+        assert_eq!(S3Key::from("").join("file.csv").as_str(), "file.csv");
+        assert_eq!(S3Key::from("dir/file.csv").file_name(), "file.csv");
+        assert_eq!(S3Key::from("dir/").file_name(), "");
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use aws_sdk_s3::{
+    error::ProvideErrorMetadata,
+    types::{AbortIncompleteMultipartUpload, BucketLifecycleConfiguration, ExpirationStatus, LifecycleExpiration, LifecycleRule, LifecycleRuleAndOperator, LifecycleRuleFilter, Tag, Transition, TransitionStorageClass},
+    Client,
+};
+use aws_smithy_types::DateTime;
+
+use crate::utils::{EXPIRATION_DAYS, EXPIRATION_TAG};
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+/// Bucket lifecycle rule applied to objects matching prefix and tags
+#[derive(Debug, Clone, PartialEq)]
+pub struct BucketLifecycleRule {
+    pub id: String,
+    pub enabled: bool,
+    pub prefix: Option<String>,
+    pub tags: HashMap<String, String>,
+    pub expiration_days: Option<i32>,
+    pub expiration_date: Option<DateTime>,
+    /// Transitions as (days after creation, storage class)
+    pub transitions: Vec<(i32, String)>,
+    pub abort_incomplete_multipart_upload_days: Option<i32>,
+}
+
+impl BucketLifecycleRule {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            enabled: true,
+            prefix: None,
+            tags: HashMap::new(),
+            expiration_days: None,
+            expiration_date: None,
+            transitions: Vec::new(),
+            abort_incomplete_multipart_upload_days: None,
+        }
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn expire_after_days(mut self, days: i32) -> Self {
+        self.expiration_days = Some(days);
+        self
+    }
+
+    pub fn expire_on(mut self, date: DateTime) -> Self {
+        self.expiration_date = Some(date);
+        self
+    }
+
+    /// Transition objects to storage class (e.g. GLACIER) days after creation
+    pub fn transition(mut self, days: i32, storage_class: impl Into<String>) -> Self {
+        self.transitions.push((days, storage_class.into()));
+        self
+    }
+
+    pub fn abort_incomplete_multipart_upload_after_days(mut self, days: i32) -> Self {
+        self.abort_incomplete_multipart_upload_days = Some(days);
+        self
+    }
+
+    fn to_sdk(&self) -> Result<LifecycleRule, UtilsError> {
+        let mut tags = self.tags
+            .iter()
+            .map(|(key, value)| Tag::builder().key(key).value(value).build())
+            .collect::<Result<Vec<_>, _>>()?;
+        let filter = match (self.prefix.as_deref(), tags.len()) {
+            (prefix, 0) => LifecycleRuleFilter::builder().prefix(prefix.unwrap_or_default()).build(),
+            (None, 1) => LifecycleRuleFilter::builder().tag(tags.remove(0)).build(),
+            (prefix, _) => {
+                let and = LifecycleRuleAndOperator::builder()
+                    .set_prefix(prefix.map(|val| val.to_string()))
+                    .set_tags(Some(tags))
+                    .build();
+                LifecycleRuleFilter::builder().and(and).build()
+            }
+        };
+        let expiration = match (self.expiration_days, self.expiration_date) {
+            (None, None) => None,
+            (days, date) => Some(
+                LifecycleExpiration::builder()
+                    .set_days(days)
+                    .set_date(date)
+                    .build()
+            ),
+        };
+        let transitions = self.transitions
+            .iter()
+            .map(|(days, storage_class)| {
+                Transition::builder()
+                    .days(*days)
+                    .storage_class(TransitionStorageClass::from(storage_class.as_str()))
+                    .build()
+            })
+            .collect();
+        let abort_incomplete_multipart_upload = self.abort_incomplete_multipart_upload_days.map(|days| {
+            AbortIncompleteMultipartUpload::builder()
+                .days_after_initiation(days)
+                .build()
+        });
+        let status = if self.enabled {
+            ExpirationStatus::Enabled
+        } else {
+            ExpirationStatus::Disabled
+        };
+
+        let rule = LifecycleRule::builder()
+            .id(&self.id)
+            .filter(filter)
+            .set_expiration(expiration)
+            .set_transitions(Some(transitions))
+            .set_abort_incomplete_multipart_upload(abort_incomplete_multipart_upload)
+            .status(status)
+            .build()?;
+
+        Ok(rule)
+    }
+}
+
+impl From<&LifecycleRule> for BucketLifecycleRule {
+    fn from(value: &LifecycleRule) -> Self {
+        let filter = value.filter();
+        let and = filter.and_then(|filter| filter.and());
+        let prefix = filter
+            .and_then(|filter| filter.prefix())
+            .or_else(|| and.and_then(|and| and.prefix()))
+            .filter(|prefix| !prefix.is_empty())
+            .map(|prefix| prefix.to_string());
+        let tags = filter
+            .and_then(|filter| filter.tag())
+            .into_iter()
+            .chain(and.map(|and| and.tags()).unwrap_or_default())
+            .map(|tag| (tag.key().to_string(), tag.value().to_string()))
+            .collect();
+
+        Self {
+            id: value.id().unwrap_or_default().to_string(),
+            enabled: *value.status() == ExpirationStatus::Enabled,
+            prefix,
+            tags,
+            expiration_days: value.expiration().and_then(|val| val.days()),
+            expiration_date: value.expiration().and_then(|val| val.date()).cloned(),
+            transitions: value
+                .transitions()
+                .iter()
+                .filter_map(|val| Some((val.days()?, val.storage_class()?.as_str().to_string())))
+                .collect(),
+            abort_incomplete_multipart_upload_days: value
+                .abort_incomplete_multipart_upload()
+                .and_then(|val| val.days_after_initiation()),
+        }
+    }
+}
+
+/// Replace bucket lifecycle configuration with rules
+pub async fn put_bucket_lifecycle(client: Client, bucket: &str, rules: &[BucketLifecycleRule]) -> Result<(), UtilsError> {
+    let rules = rules
+        .iter()
+        .map(|rule| rule.to_sdk())
+        .collect::<Result<Vec<_>, _>>()?;
+    let configuration = BucketLifecycleConfiguration::builder()
+        .set_rules(Some(rules))
+        .build()?;
+
+    client
+        .put_bucket_lifecycle_configuration()
+        .bucket(bucket)
+        .lifecycle_configuration(configuration)
+        .send()
+        .await
+        .with_context(|| ErrorContext::new("PutBucketLifecycleConfiguration").bucket(bucket))?;
+
+    Ok(())
+}
+
+/// Get bucket lifecycle rules, empty if bucket has no lifecycle configuration
+pub async fn get_bucket_lifecycle(client: Client, bucket: &str) -> Result<Vec<BucketLifecycleRule>, UtilsError> {
+    let res = client
+        .get_bucket_lifecycle_configuration()
+        .bucket(bucket)
+        .send()
+        .await;
+
+    match res {
+        Ok(res) => Ok(res.rules().iter().map(BucketLifecycleRule::from).collect()),
+        Err(sdk_err) => match sdk_err.as_service_error().and_then(|err| err.code()) {
+            Some("NoSuchLifecycleConfiguration") => Ok(Vec::new()),
+            _ => Err(UtilsError::from(sdk_err).context(ErrorContext::new("GetBucketLifecycleConfiguration").bucket(bucket))),
+        }
+    }
+}
+
+/// Get lifecycle rules expiring objects tagged with expire-after=<N>d N days after their creation,
+/// one rule for each of EXPIRATION_DAYS. Buckets are limited to 1000 rules and put_bucket_lifecycle
+/// replaces the whole configuration, so these rules are added to the other rules of bucket once
+pub fn expiration_tag_rules() -> Vec<BucketLifecycleRule> {
+    EXPIRATION_DAYS
+        .iter()
+        .map(|days| {
+            let value = expiration_tag_value(*days);
+            BucketLifecycleRule::new(format!("{}-{}", EXPIRATION_TAG, value))
+                .tag(EXPIRATION_TAG, value)
+                .expire_after_days(*days as i32)
+        })
+        .collect()
+}
+
+/// Value of expire-after tag for expiration after days
+pub(crate) fn expiration_tag_value(days: u32) -> String {
+    format!("{}d", days)
+}
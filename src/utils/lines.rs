@@ -0,0 +1,15 @@
+use aws_sdk_s3::Client;
+use futures_util::{Stream, StreamExt};
+use tokio_util::codec::{FramedRead, LinesCodec};
+
+use crate::utils::{get_aws_object_with_options, GetOptions};
+use crate::error::UtilsError;
+
+/// Stream lines of text object while it is downloaded, line endings are stripped
+pub async fn read_lines_stream(client: Client, bucket: &str, key: &str) -> Result<impl Stream<Item = Result<String, UtilsError>>, UtilsError> {
+    let res = get_aws_object_with_options(client, bucket, key, &GetOptions::default()).await?;
+    let lines = FramedRead::new(res.body.into_async_read(), LinesCodec::new())
+        .map(|line| line.map_err(UtilsError::from));
+
+    Ok(lines)
+}
@@ -0,0 +1,163 @@
+use std::{collections::HashMap, sync::Arc};
+
+use aws_sdk_s3::{types::Object, Client};
+use aws_smithy_types::DateTime;
+use tokio::{sync::Semaphore, task::JoinSet};
+
+use crate::utils::{head_objects, with_retry, ListOptions, RetryPolicy, MAX_CONCURRENCY};
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+/// AWS S3 object info from listing
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectInfo {
+    pub key: String,
+    pub size: i64,
+    pub e_tag: Option<String>,
+    pub last_modified: Option<DateTime>,
+    pub storage_class: Option<String>,
+    pub content_type: Option<String>,
+}
+
+/// Get files info, content type is guessed from key extension, 
+/// if resolve_content_types is set keys without extension are resolved with batched HeadObject requests,
+/// BucketNotFound error if bucket doesn't exist
+pub async fn list_objects_info(client: Client, bucket: &str, prefix: &str, opts: &ListOptions) -> Result<Vec<ObjectInfo>, UtilsError> {
+    let mut files = Vec::new();
+    let max_keys = opts.max_keys.unwrap_or(usize::MAX);
+    let mut continuation_token = None;
+    loop {
+        // pages are requested one by one so failed page is retried without relisting
+        let req = client
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(prefix)
+            .set_start_after(opts.start_after.clone())
+            .set_continuation_token(continuation_token);
+        let objects = with_retry(&RetryPolicy::default(), || async { Ok(req.clone().send().await?) })
+            .await
+            .map_err(|err| err.or_bucket_not_found(bucket))
+            .with_context(|| ErrorContext::object("ListObjectsV2", bucket, prefix))?;
+        files.extend(objects.contents().iter().filter_map(object_info));
+        if files.len() >= max_keys {
+            files.truncate(max_keys);
+            break;
+        }
+        continuation_token = objects.next_continuation_token().map(|val| val.to_string());
+        if continuation_token.is_none() {
+            break;
+        }
+    }
+
+    if opts.resolve_content_types {
+        let keys: Vec<String> = files
+            .iter()
+            .filter(|file| file.content_type.is_none())
+            .map(|file| file.key.clone())
+            .collect();
+        let mut metadata = head_objects(client, bucket, &keys, MAX_CONCURRENCY).await?;
+        for file in files.iter_mut().filter(|file| file.content_type.is_none()) {
+            if let Some(res) = metadata.remove(&file.key) {
+                file.content_type = res?.and_then(|val| val.content_type);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// Get files info under prefix listing each top level "directory" in parallel
+/// with at most workers ListObjectsV2 paginations in flight
+pub async fn list_objects_info_parallel(client: Client, bucket: &str, prefix: &str, workers: usize) -> Result<Vec<ObjectInfo>, UtilsError> {
+    let mut stream = client
+        .list_objects_v2()
+        .bucket(bucket)
+        .prefix(prefix)
+        .delimiter("/")
+        .into_paginator()
+        .send();
+
+    let mut files = Vec::new();
+    let mut prefixes = Vec::new();
+    while let Some(objects) = stream
+        .next()
+        .await
+        .transpose()
+        .map_err(|err| UtilsError::from(err).or_bucket_not_found(bucket))
+        .with_context(|| ErrorContext::object("ListObjectsV2", bucket, prefix))? {
+        files.extend(objects.contents().iter().filter_map(object_info));
+        prefixes.extend(objects.common_prefixes().iter().filter_map(|val| val.prefix()).map(|val| val.to_string()));
+    }
+
+    let semaphore = Arc::new(Semaphore::new(workers.max(1)));
+    let mut tasks = JoinSet::new();
+    for prefix in prefixes {
+        let client = client.clone();
+        let bucket = bucket.to_string();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire().await?;
+            list_objects_info(client, &bucket, &prefix, &ListOptions::default()).await
+        });
+    }
+
+    while let Some(res) = tasks.join_next().await {
+        files.extend(res??);
+    }
+    files.sort_by(|a, b| a.key.cmp(&b.key));
+
+    Ok(files)
+}
+
+/// Get files names
+pub async fn list_keys(client: Client, bucket: &str, prefix: &str) -> Result<Vec<String>, UtilsError> {
+	let mut stream = client
+        .list_objects_v2()
+        .prefix(prefix)
+        .bucket(bucket)
+        .into_paginator()
+        .send();
+    
+	let mut files = Vec::new();
+    while let Some(objects) = stream
+        .next()
+        .await
+        .transpose()
+        .map_err(|err| UtilsError::from(err).or_bucket_not_found(bucket))
+        .with_context(|| ErrorContext::object("ListObjectsV2", bucket, prefix))? {
+        for obj in objects.contents() {
+            if let Some(key) = obj.key() {
+                if !key.ends_with('/') {
+                    files.push(key.to_string());
+                }
+            }
+        }
+    }
+
+	Ok(files)
+}
+
+/// Get files names and size
+#[deprecated(since = "0.1.0", note = "use list_objects_info, ObjectInfo has size with ETag, last modified and storage class")]
+pub async fn list_keys_to_map(client: Client, bucket: &str, prefix: &str) -> Result<HashMap<String, i64>, UtilsError> {
+    let files = list_objects_info(client, bucket, prefix, &ListOptions::default())
+        .await?
+        .into_iter()
+        .map(|file| (file.key, file.size))
+        .collect();
+
+    Ok(files)
+}
+
+/// Get file info from listed object, None for "directory" placeholders
+fn object_info(obj: &Object) -> Option<ObjectInfo> {
+    let key = obj.key().filter(|key| !key.ends_with('/'))?;
+
+    Some(ObjectInfo {
+        key: key.to_string(),
+        size: obj.size().unwrap_or(0),
+        e_tag: obj.e_tag().map(|val| val.to_string()),
+        last_modified: obj.last_modified().cloned(),
+        storage_class: obj.storage_class().map(|val| val.as_str().to_string()),
+        content_type: mime_guess::from_path(key).first_raw().map(|val| val.to_string()),
+    })
+}
@@ -0,0 +1,533 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use aws_sdk_s3::{operation::head_object::HeadObjectOutput, types::{Checksum, CompletedMultipartUpload, CompletedPart, MetadataDirective, ObjectAttributes as AttributeName, ObjectPart as SdkObjectPart, StorageClass, Tag, Tagging}, Client};
+use aws_smithy_types::{date_time::Format, DateTime};
+use color_eyre::eyre::eyre;
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::warn;
+
+use crate::utils::{cancellable, expiration_tag_value, head_object, CancellationToken, S3Key, COPY_PART_SIZE, EXPIRATION_DAYS, EXPIRATION_TAG, MAX_CHUNKS, MAX_CONCURRENCY, MAX_COPY_SIZE};
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// AWS S3 object metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectMetadata {
+    pub size: i64,
+    pub e_tag: Option<String>,
+    pub content_type: Option<String>,
+    pub last_modified: Option<DateTime>,
+    pub storage_class: Option<String>,
+    pub metadata: HashMap<String, String>,
+}
+
+impl From<HeadObjectOutput> for ObjectMetadata {
+    fn from(value: HeadObjectOutput) -> Self {
+        Self {
+            size: value.content_length().unwrap_or(0),
+            e_tag: value.e_tag().map(|val| val.to_string()),
+            content_type: value.content_type().map(|val| val.to_string()),
+            last_modified: value.last_modified().cloned(),
+            storage_class: value.storage_class().map(|val| val.as_str().to_string()),
+            metadata: value.metadata().cloned().unwrap_or_default(),
+        }
+    }
+}
+
+/// Get object metadata, None if key doesn't exist in AWS S3
+pub async fn get_object_metadata(client: Client, bucket: &str, key: impl Into<S3Key>) -> Result<Option<ObjectMetadata>, UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let res = head_object(client, bucket, key).await?;
+
+    Ok(res.map(ObjectMetadata::from))
+}
+
+/// Get metadata of many objects concurrently with at most workers HeadObject requests in flight,
+/// errors are reported per key
+pub async fn head_objects(
+    client: Client, 
+    bucket: &str, 
+    keys: &[String], 
+    workers: usize,
+) -> Result<HashMap<String, Result<Option<ObjectMetadata>, UtilsError>>, UtilsError> {
+    let semaphore = Arc::new(Semaphore::new(workers.max(1)));
+    let mut tasks = JoinSet::new();
+    for key in keys {
+        let client = client.clone();
+        let bucket = bucket.to_string();
+        let key = key.clone();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let res = match semaphore.acquire().await {
+                Ok(_permit) => get_object_metadata(client, &bucket, &key).await,
+                Err(err) => Err(err.into()),
+            };
+            (key, res)
+        });
+    }
+
+    let mut res = HashMap::new();
+    while let Some(task) = tasks.join_next().await {
+        let (key, metadata) = task?;
+        res.insert(key, metadata);
+    }
+
+    Ok(res)
+}
+
+/// Get object tags
+pub async fn get_object_tags(client: Client, bucket: &str, key: impl Into<S3Key>) -> Result<HashMap<String, String>, UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let res = client
+        .get_object_tagging()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("GetObjectTagging", bucket, key))?;
+
+    let tags = res
+        .tag_set()
+        .iter()
+        .map(|tag| (tag.key().to_string(), tag.value().to_string()))
+        .collect();
+
+    Ok(tags)
+}
+
+/// Replace object tags
+pub async fn put_object_tags(client: Client, bucket: &str, key: impl Into<S3Key>, tags: &HashMap<String, String>) -> Result<(), UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let tag_set = tags
+        .iter()
+        .map(|(key, value)| Tag::builder().key(key).value(value).build())
+        .collect::<Result<Vec<_>, _>>()?;
+    let tagging = Tagging::builder()
+        .set_tag_set(Some(tag_set))
+        .build()?;
+
+    client
+        .put_object_tagging()
+        .bucket(bucket)
+        .key(key)
+        .tagging(tagging)
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("PutObjectTagging", bucket, key))?;
+
+    Ok(())
+}
+
+/// Delete all object tags
+pub async fn delete_object_tags(client: Client, bucket: &str, key: impl Into<S3Key>) -> Result<(), UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    client
+        .delete_object_tagging()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("DeleteObjectTagging", bucket, key))?;
+
+    Ok(())
+}
+
+/// Tag object with expire-after=<N>d, where N is the least of EXPIRATION_DAYS not shorter than ttl,
+/// existing tags are kept. Objects are removed by the rules from expiration_tag_rules N days after
+/// their creation. Returns tag value <N>d
+pub async fn set_expiration_tag(client: Client, bucket: &str, key: impl Into<S3Key>, ttl: Duration) -> Result<String, UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let value = expiration_tag_value(expiration_days(ttl)?);
+
+    let mut tags = get_object_tags(client.clone(), bucket, key).await?;
+    tags.insert(EXPIRATION_TAG.to_string(), value.clone());
+    put_object_tags(client, bucket, key, &tags).await?;
+
+    Ok(value)
+}
+
+/// Round ttl up to days of EXPIRATION_DAYS
+fn expiration_days(ttl: Duration) -> Result<u32, UtilsError> {
+    let days = ttl.as_secs().div_ceil(SECS_PER_DAY);
+    EXPIRATION_DAYS
+        .iter()
+        .copied()
+        .find(|val| *val as u64 >= days)
+        .ok_or_else(|| UtilsError::ExpirationTooLong { days, max: EXPIRATION_DAYS[EXPIRATION_DAYS.len() - 1] as u64 })
+}
+
+/// Replace object content type, storage class and user metadata in place by copying object onto itself,
+/// size, e_tag and last_modified of new_metadata are ignored. Cache-Control, Content-Disposition, Content-Encoding,
+/// Content-Language and Expires are kept, copy fails if object is overwritten meanwhile
+pub async fn update_object_metadata(client: Client, bucket: &str, key: impl Into<S3Key>, new_metadata: &ObjectMetadata) -> Result<(), UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let head = head_object(client.clone(), bucket, key)
+        .await?
+        .ok_or_else(|| UtilsError::ObjectNotFound(format!("s3://{}/{}", bucket, key)))?;
+    let size = head.content_length().unwrap_or(0) as u64;
+    let copy_source = format!("{}/{}", bucket, urlencoding::encode(key));
+    let headers = CopyHeaders {
+        content_type: new_metadata.content_type.clone(),
+        storage_class: new_metadata.storage_class.clone(),
+        metadata: Some(new_metadata.metadata.clone()),
+        ..CopyHeaders::from(&head)
+    };
+
+    if size <= MAX_COPY_SIZE {
+        client
+            .copy_object()
+            .copy_source(copy_source)
+            .set_copy_source_if_match(head.e_tag().map(|val| val.to_string()))
+            .bucket(bucket)
+            .key(key)
+            .metadata_directive(MetadataDirective::Replace)
+            .set_cache_control(headers.cache_control)
+            .set_content_disposition(headers.content_disposition)
+            .set_content_encoding(headers.content_encoding)
+            .set_content_language(headers.content_language)
+            .set_content_type(headers.content_type)
+            .set_expires(headers.expires)
+            .set_storage_class(headers.storage_class.as_deref().map(StorageClass::from))
+            .set_metadata(headers.metadata)
+            .send()
+            .await
+            .with_context(|| ErrorContext::object("CopyObject", bucket, key))?;
+
+        return Ok(());
+    }
+
+    copy_object_multipart(client, &copy_source, &head, bucket, key, &headers, None).await
+}
+
+/// Headers of object set by copy replacing metadata, taken from HeadObject of source object
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct CopyHeaders {
+    pub cache_control: Option<String>,
+    pub content_disposition: Option<String>,
+    pub content_encoding: Option<String>,
+    pub content_language: Option<String>,
+    pub content_type: Option<String>,
+    pub expires: Option<DateTime>,
+    pub storage_class: Option<String>,
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+impl From<&HeadObjectOutput> for CopyHeaders {
+    fn from(value: &HeadObjectOutput) -> Self {
+        Self {
+            cache_control: value.cache_control().map(|val| val.to_string()),
+            content_disposition: value.content_disposition().map(|val| val.to_string()),
+            content_encoding: value.content_encoding().map(|val| val.to_string()),
+            content_language: value.content_language().map(|val| val.to_string()),
+            content_type: value.content_type().map(|val| val.to_string()),
+            expires: value.expires_string().and_then(|val| DateTime::from_str(val, Format::HttpDate).ok()),
+            // like CopyObject, copies are stored in STANDARD unless storage class is set
+            storage_class: None,
+            metadata: value.metadata().cloned(),
+        }
+    }
+}
+
+/// Copy object by UploadPartCopy parts, needed above 5 GiB which is the limit of CopyObject.
+/// Parts are copied from copy_source pinned to ETag of its head, failed or cancelled upload is aborted
+pub(crate) async fn copy_object_multipart(
+    client: Client,
+    copy_source: &str,
+    head: &HeadObjectOutput,
+    bucket: &str,
+    key: &str,
+    headers: &CopyHeaders,
+    cancel: Option<&CancellationToken>,
+) -> Result<(), UtilsError> {
+    let multipart_upload_res = client
+        .create_multipart_upload()
+        .bucket(bucket)
+        .key(key)
+        .set_cache_control(headers.cache_control.clone())
+        .set_content_disposition(headers.content_disposition.clone())
+        .set_content_encoding(headers.content_encoding.clone())
+        .set_content_language(headers.content_language.clone())
+        .set_content_type(headers.content_type.clone())
+        .set_expires(headers.expires)
+        .set_storage_class(headers.storage_class.as_deref().map(StorageClass::from))
+        .set_metadata(headers.metadata.clone())
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("CreateMultipartUpload", bucket, key))?;
+
+    let upload_id = multipart_upload_res
+        .upload_id()
+        .ok_or_else(|| UtilsError::MissingUploadId(format!("s3://{}/{}", bucket, key)))?;
+    let size = head.content_length().unwrap_or_default() as u64;
+    let res = cancellable(cancel, copy_parts(client.clone(), copy_source, head.e_tag(), size, bucket, key, upload_id)).await;
+    if let Err(err) = &res {
+        warn!(bucket, key, upload_id, error = %err, "multipart copy failed, aborting");
+        // parts of failed upload are billed until upload is aborted, abort failure is only logged
+        // as original error is returned
+        let abort_res = client
+            .abort_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .send()
+            .await;
+        if let Err(abort_err) = abort_res {
+            warn!(bucket, key, upload_id, error = %abort_err, "failed to abort multipart upload");
+        }
+    }
+
+    res
+}
+
+/// Copy parts of COPY_PART_SIZE concurrently and complete upload, first failed part cancels outstanding parts
+async fn copy_parts(
+    client: Client,
+    copy_source: &str,
+    e_tag: Option<&str>,
+    size: u64,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+) -> Result<(), UtilsError> {
+    let chunk_size = COPY_PART_SIZE.max(size.div_ceil(MAX_CHUNKS));
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENCY));
+    let mut tasks = JoinSet::new();
+    for (index, start) in (0..size).step_by(chunk_size as usize).enumerate() {
+        let end = (start + chunk_size).min(size) - 1;
+        let part_number = index as i32 + 1;
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        let copy_source = copy_source.to_string();
+        let e_tag = e_tag.map(|val| val.to_string());
+        let bucket = bucket.to_string();
+        let key = key.to_string();
+        let upload_id = upload_id.to_string();
+        tasks.spawn(async move {
+            let res = async {
+                let _permit = semaphore.acquire().await?;
+                let upload_part_res = client
+                    .upload_part_copy()
+                    .copy_source(copy_source)
+                    .set_copy_source_if_match(e_tag)
+                    .copy_source_range(format!("bytes={}-{}", start, end))
+                    .bucket(&bucket)
+                    .key(&key)
+                    .upload_id(upload_id)
+                    .part_number(part_number)
+                    .send()
+                    .await
+                    .with_context(|| ErrorContext::object("UploadPartCopy", &bucket, &key).part_number(part_number))?;
+
+                let e_tag = upload_part_res
+                    .copy_part_result()
+                    .and_then(|res| res.e_tag())
+                    .ok_or_else(|| UtilsError::MissingETag(format!("s3://{}/{} part {}", bucket, key, part_number)))?;
+                Ok(CompletedPart::builder().e_tag(e_tag).part_number(part_number).build())
+            }
+            .await;
+            (part_number, res)
+        });
+    }
+
+    let mut upload_parts = Vec::new();
+    let mut failed_parts = Vec::new();
+    let mut first_err = None;
+    while let Some(res) = tasks.join_next().await {
+        let err = match res {
+            Ok((_, Ok(part))) => {
+                upload_parts.push(part);
+                continue;
+            }
+            Ok((part_number, Err(err))) => {
+                warn!(bucket, key, part = part_number, error = %err, "part copy failed");
+                failed_parts.push(part_number);
+                err
+            }
+            Err(err) if err.is_cancelled() => continue,
+            Err(err) => err.into(),
+        };
+        if first_err.is_none() {
+            tasks.abort_all();
+            first_err = Some(err);
+        }
+    }
+    if let Some(err) = first_err {
+        failed_parts.sort();
+        return Err(UtilsError::PartsFailed { parts: failed_parts, source: Box::new(err) });
+    }
+    upload_parts.sort_by_key(|part| part.part_number());
+
+    let completed_multipart_upload = CompletedMultipartUpload::builder()
+        .set_parts(Some(upload_parts))
+        .build();
+
+    client
+        .complete_multipart_upload()
+        .bucket(bucket)
+        .key(key)
+        .multipart_upload(completed_multipart_upload)
+        .upload_id(upload_id)
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("CompleteMultipartUpload", bucket, key))?;
+
+    Ok(())
+}
+
+/// AWS S3 object or part checksums (base64 encoded)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Checksums {
+    pub crc32: Option<String>,
+    pub crc32c: Option<String>,
+    pub crc64nvme: Option<String>,
+    pub sha1: Option<String>,
+    pub sha256: Option<String>,
+}
+
+impl From<&Checksum> for Checksums {
+    fn from(value: &Checksum) -> Self {
+        Self {
+            crc32: value.checksum_crc32().map(|val| val.to_string()),
+            crc32c: value.checksum_crc32_c().map(|val| val.to_string()),
+            crc64nvme: value.checksum_crc64_nvme().map(|val| val.to_string()),
+            sha1: value.checksum_sha1().map(|val| val.to_string()),
+            sha256: value.checksum_sha256().map(|val| val.to_string()),
+        }
+    }
+}
+
+impl From<&SdkObjectPart> for Checksums {
+    fn from(value: &SdkObjectPart) -> Self {
+        Self {
+            crc32: value.checksum_crc32().map(|val| val.to_string()),
+            crc32c: value.checksum_crc32_c().map(|val| val.to_string()),
+            crc64nvme: value.checksum_crc64_nvme().map(|val| val.to_string()),
+            sha1: value.checksum_sha1().map(|val| val.to_string()),
+            sha256: value.checksum_sha256().map(|val| val.to_string()),
+        }
+    }
+}
+
+/// AWS S3 multipart object part
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectPart {
+    pub part_number: i32,
+    pub size: i64,
+    pub checksums: Checksums,
+}
+
+/// AWS S3 object attributes, parts are empty for objects uploaded without multipart upload
+/// or without additional checksums
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectAttributes {
+    pub size: i64,
+    pub e_tag: Option<String>,
+    pub storage_class: Option<String>,
+    pub last_modified: Option<DateTime>,
+    pub checksums: Checksums,
+    pub parts_count: Option<i32>,
+    pub parts: Vec<ObjectPart>,
+}
+
+/// Get object attributes including all parts with their sizes and checksums
+pub async fn get_object_attributes(client: Client, bucket: &str, key: impl Into<S3Key>) -> Result<ObjectAttributes, UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let mut attributes: Option<ObjectAttributes> = None;
+    let mut part_number_marker: Option<String> = None;
+    loop {
+        let res = client
+            .get_object_attributes()
+            .bucket(bucket)
+            .key(key)
+            .object_attributes(AttributeName::Etag)
+            .object_attributes(AttributeName::Checksum)
+            .object_attributes(AttributeName::ObjectParts)
+            .object_attributes(AttributeName::StorageClass)
+            .object_attributes(AttributeName::ObjectSize)
+            .set_part_number_marker(part_number_marker.take())
+            .send()
+            .await
+            .with_context(|| ErrorContext::object("GetObjectAttributes", bucket, key))?;
+
+        let attributes = attributes.get_or_insert_with(|| ObjectAttributes {
+            size: res.object_size().unwrap_or(0),
+            e_tag: res.e_tag().map(|val| val.to_string()),
+            storage_class: res.storage_class().map(|val| val.as_str().to_string()),
+            last_modified: res.last_modified().cloned(),
+            checksums: res.checksum().map(Checksums::from).unwrap_or_default(),
+            parts_count: res.object_parts().and_then(|parts| parts.total_parts_count()),
+            parts: Vec::new(),
+        });
+
+        let Some(object_parts) = res.object_parts() else {
+            break;
+        };
+        for part in object_parts.parts() {
+            attributes.parts.push(ObjectPart {
+                part_number: part.part_number().unwrap_or(0),
+                size: part.size().unwrap_or(0),
+                checksums: Checksums::from(part),
+            });
+        }
+
+        match (object_parts.is_truncated(), object_parts.next_part_number_marker()) {
+            (Some(true), Some(marker)) => part_number_marker = Some(marker.to_string()),
+            _ => break,
+        }
+    }
+
+    attributes.ok_or_else(|| UtilsError::UnexpectedError(eyre!("Failed getting attributes for: {}", key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::expiration_tag_rules;
+
+    use super::*;
+
+    #[test]
+    fn copy_headers_keep_object_headers() {
+        let head = HeadObjectOutput::builder()
+            .cache_control("max-age=60")
+            .content_disposition("attachment")
+            .content_encoding("gzip")
+            .content_language("en")
+            .content_type("text/csv")
+            .expires_string("Wed, 21 Oct 2015 07:28:00 GMT")
+            .storage_class(StorageClass::Glacier)
+            .metadata("owner", "team")
+            .build();
+        let headers = CopyHeaders::from(&head);
+
+        assert_eq!(headers.cache_control.as_deref(), Some("max-age=60"));
+        assert_eq!(headers.content_disposition.as_deref(), Some("attachment"));
+        assert_eq!(headers.content_encoding.as_deref(), Some("gzip"));
+        assert_eq!(headers.content_language.as_deref(), Some("en"));
+        assert_eq!(headers.content_type.as_deref(), Some("text/csv"));
+        assert_eq!(headers.expires, Some(DateTime::from_secs(1_445_412_480)));
+        assert_eq!(headers.storage_class, None);
+        assert_eq!(headers.metadata, Some(HashMap::from([("owner".to_string(), "team".to_string())])));
+    }
+
+    #[test]
+    fn expiration_days_round_up() {
+        assert_eq!(expiration_days(Duration::ZERO).unwrap(), 1);
+        assert_eq!(expiration_days(Duration::from_secs(60)).unwrap(), 1);
+        assert_eq!(expiration_days(Duration::from_secs(SECS_PER_DAY + 1)).unwrap(), 7);
+        assert_eq!(expiration_days(Duration::from_secs(30 * SECS_PER_DAY)).unwrap(), 30);
+        assert!(matches!(expiration_days(Duration::from_secs(366 * SECS_PER_DAY)), Err(UtilsError::ExpirationTooLong { days: 366, max: 365 })));
+
+        let rules = expiration_tag_rules();
+        assert_eq!(rules.len(), EXPIRATION_DAYS.len());
+        assert_eq!(rules[1].tags[EXPIRATION_TAG], "7d");
+        assert_eq!(rules[1].expiration_days, Some(7));
+    }
+}
@@ -1,5 +1,135 @@
+mod batch;
+mod bucket;
+mod cancel;
+mod checksum;
+mod client;
+#[cfg(feature = "compression")]
+mod codec;
+mod compare;
 mod constants;
-mod utils;
+#[cfg(feature = "datafusion")]
+mod datafusion;
+#[cfg(feature = "csv")]
+mod csv;
+#[cfg(feature = "events")]
+mod events;
+mod filter;
+mod get;
+mod inventory;
+#[cfg(feature = "inventory")]
+mod inventory_report;
+#[cfg(feature = "json")]
+mod json;
+mod key;
+mod lifecycle;
+#[cfg(feature = "stream")]
+mod lines;
+mod list;
+mod metadata;
+mod object;
+mod object_lock;
+#[cfg(feature = "object_store")]
+mod object_store_adapter;
+mod operations;
+mod options;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod presign;
+mod progress;
+#[cfg(feature = "stream")]
+mod proxy;
+mod report;
+mod restore;
+mod retry;
+mod seekable;
+#[cfg(feature = "stream")]
+mod select;
+mod storage;
+#[cfg(feature = "sync")]
+mod sync;
+#[cfg(feature = "sync")]
+mod sync_state;
+#[cfg(feature = "tar")]
+mod tar;
+mod throttle;
+mod transfer;
+mod upload;
+mod writer;
+#[cfg(feature = "zip")]
+mod zip;
 
-pub use constants::*;
-pub use utils::*;
\ No newline at end of file
+pub use batch::{batch_manifest_location, write_batch_manifest, BatchManifest, BatchObject};
+pub use bucket::{bucket_exists, create_bucket, delete_bucket, delete_bucket_cors, get_bucket_cors, get_bucket_policy, get_bucket_region, get_public_access_block, head_bucket, list_buckets, put_bucket_cors, put_bucket_policy, put_public_access_block, Bucket, BucketInfo, CorsRule, PrefixStats, PublicAccessBlock};
+pub use cancel::CancellationToken;
+pub(crate) use cancel::cancellable;
+pub use checksum::{compute_s3_etag, compute_sha256, parse_etag, sidecar_key, verify_with_sidecar, write_sidecar, EtagKind};
+pub use client::{get_aws_client, get_aws_client_with_interceptors, get_aws_client_with_options, with_interceptor, HeaderInterceptor};
+#[cfg(feature = "compression")]
+pub use codec::Codec;
+pub use compare::{compare_with_remote, Comparison};
+#[cfg(feature = "sync")]
+pub(crate) use compare::etag_matches;
+pub use constants::{AWS_MAX_RETRIES, CHUNK_SIZE, COPY_PART_SIZE, EXPIRATION_DAYS, EXPIRATION_TAG, MAX_CHUNKS, MAX_CONCURRENCY, MAX_COPY_SIZE, MAX_DELETE_KEYS, MAX_KEY_LEN, MIN_PART_SIZE, READ_BUF_SIZE, SIDECAR_EXTENSION, TRASH_PREFIX};
+#[cfg(feature = "datafusion")]
+pub use datafusion::{register_listing_table, TableFormat};
+#[cfg(feature = "csv")]
+pub use csv::{read_csv, read_csv_stream};
+#[cfg(feature = "events")]
+pub use events::{parse_event_message, run_event_loop, S3Entity, S3EventBucket, S3EventNotification, S3EventObject, S3EventRecord};
+pub use filter::{FilterRule, Filters};
+#[allow(deprecated)]
+pub use get::{download_file, get_aws_object, get_aws_object_with_options, head_object, object_exists, read_file, read_range, try_get_file};
+pub use inventory::{put_bucket_inventory_configuration, InventoryConfig, InventoryReportFormat, InventoryReportFrequency};
+#[cfg(feature = "inventory")]
+pub use inventory_report::{inventory_records_stream, read_inventory_manifest, InventoryManifest, InventoryManifestFile, InventoryRecord};
+#[cfg(feature = "json")]
+pub use json::{read_json, read_jsonl_stream, write_json, write_jsonl_from_iter};
+pub use key::S3Key;
+pub use lifecycle::{expiration_tag_rules, get_bucket_lifecycle, put_bucket_lifecycle, BucketLifecycleRule};
+pub(crate) use lifecycle::expiration_tag_value;
+#[cfg(feature = "stream")]
+pub use lines::read_lines_stream;
+#[allow(deprecated)]
+pub use list::{list_keys, list_keys_to_map, list_objects_info, list_objects_info_parallel, ObjectInfo};
+pub use metadata::{delete_object_tags, get_object_attributes, get_object_metadata, get_object_tags, head_objects, put_object_tags, set_expiration_tag, update_object_metadata, Checksums, ObjectAttributes, ObjectMetadata, ObjectPart};
+pub(crate) use metadata::{copy_object_multipart, CopyHeaders};
+pub use object::S3Object;
+pub use object_lock::{get_legal_hold, get_object_retention, put_object_retention, set_legal_hold, ObjectRetention, RetentionMode};
+#[cfg(feature = "object_store")]
+pub use object_store_adapter::S3ObjectStore;
+pub use operations::{copy_object, copy_prefix, delete_keys, delete_object, delete_prefix, download_dir, move_to_local, move_to_s3, restore_from_trash, upload_dir, S3Path, SymlinkPolicy};
+#[cfg(feature = "sync")]
+pub(crate) use operations::{cancellable_copy_object, list_local_files, local_path, LocalFile};
+pub use options::{ClientOptions, CopyOptions, GetOptions, ListOptions, PutOptions};
+#[cfg(feature = "parquet")]
+pub use parquet::{read_parquet, read_parquet_columns, S3ParquetReader};
+pub use presign::{abort_presigned_upload, complete_presigned_upload, create_presigned_upload, download_presigned, presign_delete, presign_get, presign_get_many, presign_head, presign_post, presign_put, read_presigned, verify_presigned_url, PostConditions, PresignedMultipartUpload, PresignedPost, PresignedUrl, PresignedUrlInfo, ResponseOverrides, SignedHeaders, UploadedPart};
+pub use progress::ProgressReporter;
+#[cfg(feature = "indicatif")]
+pub use progress::IndicatifReporter;
+#[cfg(feature = "progress-bars")]
+pub use progress::MultiProgressReporter;
+#[cfg(feature = "stream")]
+pub use proxy::{stream_to_http_body, HttpObject};
+pub use report::TransferReport;
+pub(crate) use report::RetryCounter;
+pub use restore::{restore_object, restore_prefix, restore_status, wait_for_restore, RestoreStatus, RestoreTier};
+pub use retry::{is_retryable_error, with_retry, RetryPolicy};
+pub(crate) use retry::{RETRYABLE_CODES, THROTTLING_CODES};
+pub use seekable::S3SeekableReader;
+#[cfg(feature = "stream")]
+pub use select::{select_object_content, SelectInput, SelectOutput};
+pub use storage::{InMemoryStorage, S3Storage};
+#[cfg(feature = "sync")]
+pub use sync::{plan_sync_both, plan_sync_down, plan_sync_s3, plan_sync_up, sync_both, sync_down, sync_s3, sync_up, CompareStrategy, ConflictPolicy, SyncAction, SyncActionKind, SyncEvent, SyncHook, SyncOptions, SyncPlan, SyncReport};
+#[cfg(feature = "sync")]
+pub use sync_state::{FileState, SyncState};
+#[cfg(feature = "tar")]
+pub use tar::extract_tar;
+pub use throttle::RateLimiter;
+pub use transfer::TransferManager;
+#[allow(deprecated)]
+pub use upload::{upload_file, upload_object_multipart, upload_object_multipart_with_options};
+pub use writer::S3Writer;
+#[cfg(feature = "zip")]
+pub use zip::{zip_extract_file, zip_list, ZipMember};
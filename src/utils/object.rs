@@ -0,0 +1,73 @@
+use std::{path::Path, time::Duration};
+
+use aws_sdk_s3::Client;
+
+use crate::utils::{delete_object, object_exists, presign_get, read_file, ResponseOverrides, S3Key, S3Path, TransferManager, TransferReport};
+use crate::error::UtilsError;
+
+/// Handle to single AWS S3 object, key is validated before every request
+#[derive(Debug, Clone)]
+pub struct S3Object {
+    client: Client,
+    path: S3Path,
+}
+
+impl S3Object {
+    pub fn new(client: Client, bucket: impl Into<String>, key: impl Into<S3Key>) -> Self {
+        Self {
+            client,
+            path: S3Path::new(bucket, key.into()),
+        }
+    }
+
+    pub fn from_path(client: Client, path: S3Path) -> Self {
+        Self { client, path }
+    }
+
+    pub fn bucket(&self) -> &str {
+        &self.path.bucket
+    }
+
+    pub fn key(&self) -> &str {
+        &self.path.key
+    }
+
+    pub fn path(&self) -> &S3Path {
+        &self.path
+    }
+
+    /// Check if object exists
+    pub async fn exists(&self) -> Result<bool, UtilsError> {
+        object_exists(self.client.clone(), &self.path.bucket, &self.checked_path()?.key).await
+    }
+
+    /// Read whole object
+    pub async fn read(&self) -> Result<Vec<u8>, UtilsError> {
+        read_file(self.client.clone(), &self.path.bucket, &self.checked_path()?.key).await
+    }
+
+    /// Download object to local file, large objects by parallel ranged requests
+    pub async fn download(&self, file_path: impl AsRef<Path>) -> Result<TransferReport, UtilsError> {
+        TransferManager::new(self.client.clone()).download(self.checked_path()?, file_path).await
+    }
+
+    /// Upload local file to this object, large files by multipart upload
+    pub async fn upload_from(&self, file_path: impl AsRef<Path>) -> Result<TransferReport, UtilsError> {
+        TransferManager::new(self.client.clone()).upload(file_path, self.checked_path()?).await
+    }
+
+    /// Delete object
+    pub async fn delete(&self) -> Result<(), UtilsError> {
+        delete_object(self.client.clone(), &self.path.bucket, &self.checked_path()?.key, None).await
+    }
+
+    /// Get presigned GetObject url
+    pub async fn presign_get(&self, expires_in: Duration, overrides: Option<&ResponseOverrides>) -> Result<String, UtilsError> {
+        presign_get(self.client.clone(), &self.path.bucket, &self.checked_path()?.key, expires_in, overrides).await
+    }
+
+    fn checked_path(&self) -> Result<&S3Path, UtilsError> {
+        S3Key::from(self.path.key.as_str()).validate()?;
+        Ok(&self.path)
+    }
+}
@@ -0,0 +1,130 @@
+use aws_sdk_s3::{error::ProvideErrorMetadata, types::{ObjectLockLegalHold, ObjectLockLegalHoldStatus, ObjectLockRetention, ObjectLockRetentionMode}, Client};
+use aws_smithy_types::DateTime;
+
+use crate::utils::S3Key;
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+/// Turn legal hold on or off for object in object lock-enabled bucket
+pub async fn set_legal_hold(client: Client, bucket: &str, key: impl Into<S3Key>, on: bool) -> Result<(), UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let status = if on {
+        ObjectLockLegalHoldStatus::On
+    } else {
+        ObjectLockLegalHoldStatus::Off
+    };
+    let legal_hold = ObjectLockLegalHold::builder()
+        .status(status)
+        .build();
+
+    client
+        .put_object_legal_hold()
+        .bucket(bucket)
+        .key(key)
+        .legal_hold(legal_hold)
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("PutObjectLegalHold", bucket, key))?;
+
+    Ok(())
+}
+
+/// Check if legal hold is on for object in object lock-enabled bucket
+pub async fn get_legal_hold(client: Client, bucket: &str, key: impl Into<S3Key>) -> Result<bool, UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let res = client
+        .get_object_legal_hold()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("GetObjectLegalHold", bucket, key))?;
+
+    let on = res
+        .legal_hold()
+        .and_then(|legal_hold| legal_hold.status())
+        .is_some_and(|status| *status == ObjectLockLegalHoldStatus::On);
+
+    Ok(on)
+}
+
+/// Object lock retention mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionMode {
+    Governance,
+    Compliance,
+}
+
+/// Object lock retention settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectRetention {
+    pub mode: RetentionMode,
+    pub retain_until: DateTime,
+}
+
+/// Get retention settings of object in object lock-enabled bucket, None if object has no retention
+pub async fn get_object_retention(client: Client, bucket: &str, key: impl Into<S3Key>) -> Result<Option<ObjectRetention>, UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let res = client
+        .get_object_retention()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await;
+
+    let res = match res {
+        Ok(res) => res,
+        Err(sdk_err) => match sdk_err.as_service_error().and_then(|err| err.code()) {
+            Some("NoSuchObjectLockConfiguration") => return Ok(None),
+            _ => return Err(UtilsError::from(sdk_err).context(ErrorContext::object("GetObjectRetention", bucket, key))),
+        }
+    };
+
+    let Some(retention) = res.retention() else {
+        return Ok(None);
+    };
+    let (Some(mode), Some(retain_until)) = (retention.mode(), retention.retain_until_date()) else {
+        return Ok(None);
+    };
+    let mode = match mode {
+        ObjectLockRetentionMode::Compliance => RetentionMode::Compliance,
+        _ => RetentionMode::Governance,
+    };
+
+    Ok(Some(ObjectRetention { mode, retain_until: *retain_until }))
+}
+
+/// Set retention settings of object in object lock-enabled bucket,
+/// bypass_governance allows shortening or removing governance mode retention
+pub async fn put_object_retention(
+    client: Client, 
+    bucket: &str, 
+    key: impl Into<S3Key>, 
+    retention: &ObjectRetention, 
+    bypass_governance: bool,
+) -> Result<(), UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let mode = match retention.mode {
+        RetentionMode::Governance => ObjectLockRetentionMode::Governance,
+        RetentionMode::Compliance => ObjectLockRetentionMode::Compliance,
+    };
+    let retention = ObjectLockRetention::builder()
+        .mode(mode)
+        .retain_until_date(retention.retain_until)
+        .build();
+
+    client
+        .put_object_retention()
+        .bucket(bucket)
+        .key(key)
+        .retention(retention)
+        .bypass_governance_retention(bypass_governance)
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("PutObjectRetention", bucket, key))?;
+
+    Ok(())
+}
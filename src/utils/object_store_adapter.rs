@@ -0,0 +1,396 @@
+use std::{fmt, sync::{Arc, Mutex}};
+
+use async_trait::async_trait;
+use aws_sdk_s3::{primitives::ByteStream, types::{CompletedMultipartUpload, CompletedPart}, Client};
+use aws_smithy_types::DateTime;
+use bytes::Bytes;
+use chrono::Utc;
+use futures_util::{stream::{self, BoxStream}, StreamExt};
+use object_store::{
+    path::Path, CopyMode, CopyOptions as StoreCopyOptions, Error as StoreError, GetOptions as StoreGetOptions, GetRange, GetResult,
+    GetResultPayload, ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutMode, PutMultipartOptions, PutOptions as StorePutOptions,
+    PutPayload, PutResult, UploadPart,
+};
+
+use crate::utils::{copy_object, head_object, list_objects_info, CopyOptions, ListOptions, ObjectInfo, S3Path};
+use crate::error::UtilsError;
+
+const STORE: &str = "S3";
+
+/// object_store::ObjectStore on top of AWS S3 Client for one bucket,
+/// to use the same client with Arrow, DataFusion or delta-rs
+#[derive(Debug, Clone)]
+pub struct S3ObjectStore {
+    client: Client,
+    bucket: String,
+}
+
+impl S3ObjectStore {
+    pub fn new(client: Client, bucket: impl Into<String>) -> Self {
+        Self {
+            client,
+            bucket: bucket.into(),
+        }
+    }
+}
+
+impl fmt::Display for S3ObjectStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "S3ObjectStore(s3://{})", self.bucket)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for S3ObjectStore {
+    async fn put_opts(&self, location: &Path, payload: PutPayload, opts: StorePutOptions) -> object_store::Result<PutResult> {
+        let mut req = self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(location.as_ref())
+            .body(ByteStream::from(Bytes::from(payload)));
+        req = match opts.mode {
+            PutMode::Overwrite => req,
+            PutMode::Create => req.if_none_match("*"),
+            PutMode::Update(version) => req.set_if_match(version.e_tag),
+        };
+        let res = req.send().await.map_err(|err| store_error(location, err.into()))?;
+
+        Ok(PutResult {
+            e_tag: res.e_tag().map(|val| val.to_string()),
+            version: res.version_id().map(|val| val.to_string()),
+        })
+    }
+
+    async fn put_multipart_opts(&self, location: &Path, _opts: PutMultipartOptions) -> object_store::Result<Box<dyn MultipartUpload>> {
+        let res = self.client
+            .create_multipart_upload()
+            .bucket(&self.bucket)
+            .key(location.as_ref())
+            .send()
+            .await
+            .map_err(|err| store_error(location, err.into()))?;
+        let upload_id = res.upload_id().unwrap_or_default().to_string();
+
+        Ok(Box::new(S3MultipartUpload {
+            client: self.client.clone(),
+            path: S3Path::from_object_store(&self.bucket, location),
+            upload_id,
+            part_number: 0,
+            parts: Arc::default(),
+        }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: StoreGetOptions) -> object_store::Result<GetResult> {
+        if options.head {
+            let head = head_object(self.client.clone(), &self.bucket, location.as_ref())
+                .await
+                .map_err(|err| store_error(location, err))?
+                .ok_or_else(|| not_found(location))?;
+            let meta = ObjectMeta {
+                location: location.clone(),
+                last_modified: to_chrono(head.last_modified()),
+                size: head.content_length().unwrap_or(0) as u64,
+                e_tag: head.e_tag().map(|val| val.to_string()),
+                version: head.version_id().map(|val| val.to_string()),
+            };
+            return Ok(GetResult {
+                payload: GetResultPayload::Stream(stream::empty().boxed()),
+                range: 0..meta.size,
+                meta,
+                attributes: Default::default(),
+            });
+        }
+
+        let range = options.range.map(|range| match range {
+            GetRange::Bounded(range) => format!("bytes={}-{}", range.start, range.end.saturating_sub(1)),
+            GetRange::Offset(offset) => format!("bytes={}-", offset),
+            GetRange::Suffix(len) => format!("bytes=-{}", len),
+        });
+        let res = self.client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(location.as_ref())
+            .set_range(range)
+            .set_version_id(options.version)
+            .set_if_match(options.if_match)
+            .set_if_none_match(options.if_none_match)
+            .set_if_modified_since(options.if_modified_since.map(to_aws))
+            .set_if_unmodified_since(options.if_unmodified_since.map(to_aws))
+            .send()
+            .await
+            .map_err(|err| store_error(location, err.into()))?;
+
+        let len = res.content_length().unwrap_or(0) as u64;
+        // Content-Range is bytes first-last/size
+        let (range, size) = res
+            .content_range()
+            .and_then(|val| val.strip_prefix("bytes "))
+            .and_then(|val| val.split_once('/'))
+            .and_then(|(range, size)| {
+                let (first, _) = range.split_once('-')?;
+                let first: u64 = first.parse().ok()?;
+                Some((first..first + len, size.parse().ok()?))
+            })
+            .unwrap_or((0..len, len));
+        let meta = ObjectMeta {
+            location: location.clone(),
+            last_modified: to_chrono(res.last_modified()),
+            size,
+            e_tag: res.e_tag().map(|val| val.to_string()),
+            version: res.version_id().map(|val| val.to_string()),
+        };
+        let location = location.clone();
+        let body = stream::unfold(res.body, move |mut body| {
+            let location = location.clone();
+            async move {
+                match body.try_next().await {
+                    Ok(Some(bytes)) => Some((Ok(bytes), body)),
+                    Ok(None) => None,
+                    Err(err) => Some((Err(store_error(&location, err.into())), body)),
+                }
+            }
+        });
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(body.boxed()),
+            meta,
+            range,
+            attributes: Default::default(),
+        })
+    }
+
+    fn delete_stream(&self, locations: BoxStream<'static, object_store::Result<Path>>) -> BoxStream<'static, object_store::Result<Path>> {
+        let client = self.client.clone();
+        let bucket = self.bucket.clone();
+        locations
+            .then(move |location| {
+                let client = client.clone();
+                let bucket = bucket.clone();
+                async move {
+                    let location = location?;
+                    client
+                        .delete_object()
+                        .bucket(bucket)
+                        .key(location.as_ref())
+                        .send()
+                        .await
+                        .map_err(|err| store_error(&location, err.into()))?;
+                    Ok(location)
+                }
+            })
+            .boxed()
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        let client = self.client.clone();
+        let bucket = self.bucket.clone();
+        let prefix = prefix_key(prefix);
+        stream::once(async move {
+            let files = list_objects_info(client, &bucket, &prefix, &ListOptions::default())
+                .await
+                .map_err(|err| store_error(&Path::from(prefix.as_str()), err))?;
+            Ok::<_, StoreError>(stream::iter(files.into_iter().map(object_meta)))
+        })
+        .map(|res| match res {
+            Ok(files) => files.left_stream(),
+            Err(err) => stream::iter([Err(err)]).right_stream(),
+        })
+        .flatten()
+        .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        let key_prefix = prefix_key(prefix);
+        let mut stream = self.client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(&key_prefix)
+            .delimiter("/")
+            .into_paginator()
+            .send();
+
+        let mut res = ListResult {
+            common_prefixes: Vec::new(),
+            objects: Vec::new(),
+        };
+        while let Some(page) = stream.next().await.transpose().map_err(|err| store_error(&Path::from(key_prefix.as_str()), err.into()))? {
+            for common_prefix in page.common_prefixes().iter().filter_map(|val| val.prefix()) {
+                res.common_prefixes.push(Path::parse(common_prefix.trim_end_matches('/'))?);
+            }
+            for object in page.contents() {
+                let Some(key) = object.key().filter(|key| !key.ends_with('/')) else {
+                    continue;
+                };
+                res.objects.push(ObjectMeta {
+                    location: Path::parse(key)?,
+                    last_modified: to_chrono(object.last_modified()),
+                    size: object.size().unwrap_or(0) as u64,
+                    e_tag: object.e_tag().map(|val| val.to_string()),
+                    version: None,
+                });
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// With CopyMode::Create existence of destination is checked before copy, which is not atomic
+    async fn copy_opts(&self, from: &Path, to: &Path, options: StoreCopyOptions) -> object_store::Result<()> {
+        if options.mode == CopyMode::Create {
+            let exists = head_object(self.client.clone(), &self.bucket, to.as_ref())
+                .await
+                .map_err(|err| store_error(to, err))?
+                .is_some();
+            if exists {
+                return Err(StoreError::AlreadyExists {
+                    path: to.to_string(),
+                    source: format!("s3://{}/{} already exists", self.bucket, to).into(),
+                });
+            }
+        }
+
+        let src = S3Path::from_object_store(&self.bucket, from);
+        let dst = S3Path::from_object_store(&self.bucket, to);
+        copy_object(self.client.clone(), &src, &dst, &CopyOptions::default())
+            .await
+            .map_err(|err| store_error(from, err))
+    }
+}
+
+impl From<&S3Path> for Path {
+    fn from(path: &S3Path) -> Self {
+        Path::from(path.key.as_str())
+    }
+}
+
+impl S3Path {
+    /// Get S3Path of object_store path in bucket
+    pub fn from_object_store(bucket: impl Into<String>, path: &Path) -> Self {
+        S3Path::new(bucket, path.as_ref())
+    }
+}
+
+/// Multipart upload started by S3ObjectStore, parts may be uploaded concurrently
+#[derive(Debug)]
+struct S3MultipartUpload {
+    client: Client,
+    path: S3Path,
+    upload_id: String,
+    part_number: i32,
+    parts: Arc<Mutex<Vec<CompletedPart>>>,
+}
+
+#[async_trait]
+impl MultipartUpload for S3MultipartUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        self.part_number += 1;
+        let part_number = self.part_number;
+        let client = self.client.clone();
+        let path = self.path.clone();
+        let upload_id = self.upload_id.clone();
+        let parts = self.parts.clone();
+        Box::pin(async move {
+            let res = client
+                .upload_part()
+                .bucket(&path.bucket)
+                .key(&path.key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(ByteStream::from(Bytes::from(data)))
+                .send()
+                .await
+                .map_err(|err| store_error(&Path::from(path.key.as_str()), err.into()))?;
+            let part = CompletedPart::builder()
+                .set_e_tag(res.e_tag().map(|val| val.to_string()))
+                .part_number(part_number)
+                .build();
+            parts
+                .lock()
+                .map_err(|_| store_error(&Path::from(path.key.as_str()), UtilsError::UnexpectedError(color_eyre::eyre::eyre!("Parts lock poisoned"))))?
+                .push(part);
+            Ok(())
+        })
+    }
+
+    async fn complete(&mut self) -> object_store::Result<PutResult> {
+        let location = Path::from(self.path.key.as_str());
+        let mut parts = self.parts
+            .lock()
+            .map_err(|_| store_error(&location, UtilsError::UnexpectedError(color_eyre::eyre::eyre!("Parts lock poisoned"))))?
+            .clone();
+        parts.sort_by_key(|part| part.part_number());
+        let res = self.client
+            .complete_multipart_upload()
+            .bucket(&self.path.bucket)
+            .key(&self.path.key)
+            .upload_id(&self.upload_id)
+            .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+            .send()
+            .await
+            .map_err(|err| store_error(&location, err.into()))?;
+
+        Ok(PutResult {
+            e_tag: res.e_tag().map(|val| val.to_string()),
+            version: res.version_id().map(|val| val.to_string()),
+        })
+    }
+
+    async fn abort(&mut self) -> object_store::Result<()> {
+        self.client
+            .abort_multipart_upload()
+            .bucket(&self.path.bucket)
+            .key(&self.path.key)
+            .upload_id(&self.upload_id)
+            .send()
+            .await
+            .map_err(|err| store_error(&Path::from(self.path.key.as_str()), err.into()))?;
+
+        Ok(())
+    }
+}
+
+/// Map error by HTTP status code to object_store error
+fn store_error(location: &Path, err: UtilsError) -> StoreError {
+    let path = location.to_string();
+    match err.status_code() {
+        Some(404) => StoreError::NotFound { path, source: Box::new(err) },
+        Some(304) => StoreError::NotModified { path, source: Box::new(err) },
+        Some(412) => StoreError::Precondition { path, source: Box::new(err) },
+        _ => StoreError::Generic { store: STORE, source: Box::new(err) },
+    }
+}
+
+fn not_found(location: &Path) -> StoreError {
+    StoreError::NotFound {
+        path: location.to_string(),
+        source: format!("{} not found", location).into(),
+    }
+}
+
+/// Get key prefix of all objects under path
+fn prefix_key(prefix: Option<&Path>) -> String {
+    match prefix {
+        Some(prefix) if !prefix.as_ref().is_empty() => format!("{}/", prefix),
+        _ => String::new(),
+    }
+}
+
+fn object_meta(file: ObjectInfo) -> object_store::Result<ObjectMeta> {
+    Ok(ObjectMeta {
+        location: Path::parse(&file.key)?,
+        last_modified: to_chrono(file.last_modified.as_ref()),
+        size: file.size as u64,
+        e_tag: file.e_tag,
+        version: None,
+    })
+}
+
+fn to_chrono(date_time: Option<&DateTime>) -> chrono::DateTime<Utc> {
+    date_time
+        .and_then(|val| chrono::DateTime::from_timestamp(val.secs(), val.subsec_nanos()))
+        .unwrap_or_default()
+}
+
+fn to_aws(date_time: chrono::DateTime<Utc>) -> DateTime {
+    DateTime::from_secs(date_time.timestamp())
+}
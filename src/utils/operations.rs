@@ -0,0 +1,641 @@
+use std::{collections::HashSet, fmt, path::{Component, Path, PathBuf}, str::FromStr, sync::Arc, time::SystemTime};
+
+use aws_sdk_s3::{types::{Delete, MetadataDirective, ObjectIdentifier, StorageClass}, Client};
+use aws_smithy_types::{date_time::Format, DateTime};
+use color_eyre::eyre::eyre;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use tokio::{fs, sync::Semaphore, task::JoinSet};
+use url::Url;
+
+use crate::utils::{cancellable, download_file, head_object, list_keys, list_objects_info, upload_file, upload_object_multipart_with_options, copy_object_multipart, CancellationToken, CopyHeaders, CopyOptions, Filters, ListOptions, ProgressReporter, PutOptions, S3Key, TransferManager, CHUNK_SIZE, MAX_CONCURRENCY, MAX_COPY_SIZE, MAX_DELETE_KEYS, TRASH_PREFIX};
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+/// AWS S3 location: bucket and key (or key prefix), serialized as uri like s3://bucket/key
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(try_from = "String", into = "String"))]
+pub struct S3Path {
+    pub bucket: String,
+    pub key: String,
+}
+
+impl S3Path {
+    pub fn new(bucket: impl Into<String>, key: impl Into<String>) -> Self {
+        Self {
+            bucket: bucket.into(),
+            key: key.into(),
+        }
+    }
+
+    /// Parse S3Path from uri like s3://bucket/key
+    pub fn from_uri(uri: &str) -> Result<Self, UtilsError> {
+        let path = uri
+            .strip_prefix("s3://")
+            .ok_or_else(|| UtilsError::UnexpectedError(eyre!("Bad S3 uri: {}", uri)))?;
+        let (bucket, key) = path.split_once('/').unwrap_or((path, ""));
+        if bucket.is_empty() {
+            return Err(UtilsError::UnexpectedError(eyre!("Missing bucket in S3 uri: {}", uri)));
+        }
+
+        Ok(Self::new(bucket, key))
+    }
+
+    /// Get uri like s3://bucket/key
+    pub fn uri(&self) -> String {
+        self.to_string()
+    }
+
+    /// Parse S3Path from s3:// uri or https url in virtual-hosted style like https://bucket.s3.region.amazonaws.com/key
+    /// or path style like https://s3.region.amazonaws.com/bucket/key
+    pub fn from_url(url: &str) -> Result<Self, UtilsError> {
+        if url.starts_with("s3://") {
+            return Self::from_uri(url);
+        }
+
+        let parsed = Url::parse(url).map_err(|e| UtilsError::UnexpectedError(e.into()))?;
+        let host = parsed.host_str().unwrap_or_default();
+        let path = urlencoding::decode(parsed.path().trim_start_matches('/'))
+            .map_err(|e| UtilsError::UnexpectedError(e.into()))?;
+        let Some(host) = host.strip_suffix(".amazonaws.com") else {
+            return Err(UtilsError::UnexpectedError(eyre!("Not AWS S3 url: {}", url)));
+        };
+        match host.rsplit_once(".s3") {
+            Some((bucket, _)) if !bucket.is_empty() => Ok(Self::new(bucket, path.into_owned())),
+            _ if host == "s3" || host.starts_with("s3.") || host.starts_with("s3-") => {
+                let (bucket, key) = path.split_once('/').unwrap_or((&path, ""));
+                if bucket.is_empty() {
+                    return Err(UtilsError::UnexpectedError(eyre!("Missing bucket in S3 url: {}", url)));
+                }
+                Ok(Self::new(bucket, key))
+            }
+            _ => Err(UtilsError::UnexpectedError(eyre!("Not AWS S3 url: {}", url))),
+        }
+    }
+
+    /// Get virtual-hosted style url like https://bucket.s3.region.amazonaws.com/key
+    pub fn https_url(&self, region: &str) -> String {
+        let key: Vec<_> = self.key.split('/').map(urlencoding::encode).collect();
+        format!("https://{}.s3.{}.amazonaws.com/{}", self.bucket, region, key.join("/"))
+    }
+
+    /// Get S3Path in the same bucket with key appended to this path
+    pub fn join(&self, key: &str) -> Self {
+        let key = key.trim_start_matches('/');
+        let key = if self.key.is_empty() || self.key.ends_with('/') {
+            format!("{}{}", self.key, key)
+        } else {
+            format!("{}/{}", self.key, key)
+        };
+
+        Self::new(self.bucket.clone(), key)
+    }
+}
+
+impl fmt::Display for S3Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "s3://{}/{}", self.bucket, self.key)
+    }
+}
+
+impl FromStr for S3Path {
+    type Err = UtilsError;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        Self::from_uri(uri)
+    }
+}
+
+impl TryFrom<String> for S3Path {
+    type Error = UtilsError;
+
+    fn try_from(uri: String) -> Result<Self, Self::Error> {
+        Self::from_uri(&uri)
+    }
+}
+
+impl TryFrom<&Url> for S3Path {
+    type Error = UtilsError;
+
+    fn try_from(url: &Url) -> Result<Self, Self::Error> {
+        Self::from_url(url.as_str())
+    }
+}
+
+impl From<S3Path> for String {
+    fn from(path: S3Path) -> Self {
+        path.to_string()
+    }
+}
+
+/// Copy object server-side pinned to ETag of source, objects above 5 GiB limit of CopyObject
+/// are copied by UploadPartCopy parts
+pub async fn copy_object(client: Client, src: &S3Path, dst: &S3Path, opts: &CopyOptions) -> Result<(), UtilsError> {
+    cancellable_copy_object(client, src, dst, opts, None).await
+}
+
+/// Copy object like copy_object stopped by cancel with Cancelled error, cancelled multipart copy is aborted
+pub(crate) async fn cancellable_copy_object(
+    client: Client,
+    src: &S3Path,
+    dst: &S3Path,
+    opts: &CopyOptions,
+    cancel: Option<&CancellationToken>,
+) -> Result<(), UtilsError> {
+    let head = client
+        .head_object()
+        .bucket(&src.bucket)
+        .key(&src.key)
+        .set_version_id(opts.version_id.clone())
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("HeadObject", &src.bucket, &src.key))?;
+    let mut copy_source = format!("{}/{}", src.bucket, urlencoding::encode(&src.key));
+    if let Some(version_id) = &opts.version_id {
+        copy_source = format!("{}?versionId={}", copy_source, urlencoding::encode(version_id));
+    }
+    let replace = opts.content_type.is_some() || opts.metadata.is_some();
+    let source_headers = CopyHeaders::from(&head);
+    let headers = CopyHeaders {
+        content_type: opts.content_type.clone().or(source_headers.content_type.clone()),
+        storage_class: opts.storage_class.clone(),
+        metadata: opts.metadata.clone().or(source_headers.metadata.clone()),
+        ..source_headers
+    };
+
+    let size = head.content_length().unwrap_or_default() as u64;
+    if size > MAX_COPY_SIZE {
+        return copy_object_multipart(client, &copy_source, &head, &dst.bucket, &dst.key, &headers, cancel).await;
+    }
+
+    // with Replace directive headers of source not set again would be dropped
+    let req = client
+        .copy_object()
+        .copy_source(copy_source)
+        .set_copy_source_if_match(head.e_tag().map(|val| val.to_string()))
+        .bucket(&dst.bucket)
+        .key(&dst.key)
+        .set_storage_class(headers.storage_class.as_deref().map(StorageClass::from));
+    let req = if replace {
+        req
+            .metadata_directive(MetadataDirective::Replace)
+            .set_cache_control(headers.cache_control)
+            .set_content_disposition(headers.content_disposition)
+            .set_content_encoding(headers.content_encoding)
+            .set_content_language(headers.content_language)
+            .set_content_type(headers.content_type)
+            .set_expires(headers.expires)
+            .set_metadata(headers.metadata)
+    } else {
+        req
+    };
+    cancellable(cancel, async {
+        req.send().await.with_context(|| ErrorContext::object("CopyObject", &dst.bucket, &dst.key))
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Copy all objects under prefix server-side in parallel, preserving relative paths
+pub async fn copy_prefix(client: Client, src_prefix: S3Path, dst_prefix: S3Path) -> Result<(), UtilsError> {
+    let keys = list_keys(client.clone(), &src_prefix.bucket, &src_prefix.key).await?;
+    let objects = keys
+        .into_iter()
+        .map(|key| {
+            let relative = key.strip_prefix(&src_prefix.key).unwrap_or(&key).to_string();
+            let src = S3Path::new(src_prefix.bucket.clone(), key);
+            let dst = S3Path::new(dst_prefix.bucket.clone(), format!("{}{}", dst_prefix.key, relative));
+            (src, dst)
+        })
+        .collect();
+
+    copy_objects(client, objects).await
+}
+
+/// Copy objects server-side in parallel with copy_object
+async fn copy_objects(client: Client, objects: Vec<(S3Path, S3Path)>) -> Result<(), UtilsError> {
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENCY));
+    let mut tasks = JoinSet::new();
+    for (src, dst) in objects {
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire().await?;
+            copy_object(client, &src, &dst, &CopyOptions::default()).await
+        });
+    }
+
+    while let Some(res) = tasks.join_next().await {
+        res??;
+    }
+
+    Ok(())
+}
+
+/// Delete object, if trash_prefix is set object is copied to <trash_prefix>/<date>/<key> first,
+/// objects above 5 GiB by UploadPartCopy parts
+pub async fn delete_object(client: Client, bucket: &str, key: impl Into<S3Key>, trash_prefix: Option<&str>) -> Result<(), UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    if let Some(trash_prefix) = trash_prefix {
+        let src = S3Path::new(bucket, key);
+        let dst = trash_path(bucket, trash_prefix, &today()?).join(key);
+        copy_object(client.clone(), &src, &dst, &CopyOptions::default()).await?;
+    }
+
+    client
+        .delete_object()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("DeleteObject", bucket, key))?;
+
+    Ok(())
+}
+
+/// Delete all objects under prefix passing filters (matched against key relative to prefix),
+/// if trash_prefix is set objects are copied to <trash_prefix>/<date>/<key> first,
+/// objects above 5 GiB by UploadPartCopy parts
+pub async fn delete_prefix(
+    client: Client,
+    bucket: &str,
+    prefix: &str,
+    trash_prefix: Option<&str>,
+    filters: Option<&Filters>,
+) -> Result<(), UtilsError> {
+    let mut keys = list_keys(client.clone(), bucket, prefix).await?;
+    if let Some(filters) = filters {
+        keys.retain(|key| filters.is_included(key.strip_prefix(prefix).unwrap_or(key)));
+    }
+    if let Some(trash_prefix) = trash_prefix {
+        keys.retain(|key| !key.starts_with(trash_prefix));
+        let trash = trash_path(bucket, trash_prefix, &today()?);
+        let objects = keys
+            .iter()
+            .map(|key| (S3Path::new(bucket, key.clone()), trash.join(key)))
+            .collect();
+        copy_objects(client.clone(), objects).await?;
+    }
+
+    delete_keys(client, bucket, &keys).await
+}
+
+/// Restore objects under prefix deleted on date (YYYY-MM-DD) from trash
+pub async fn restore_from_trash(
+    client: Client,
+    bucket: &str,
+    prefix: &str,
+    date: &str, 
+    trash_prefix: Option<&str>,
+) -> Result<(), UtilsError> {
+    let trash_prefix = trash_prefix.unwrap_or(TRASH_PREFIX);
+    let src = trash_path(bucket, trash_prefix, date).join(prefix);
+    let dst = S3Path::new(bucket, prefix);
+    copy_prefix(client.clone(), src.clone(), dst).await?;
+
+    let keys = list_keys(client.clone(), &src.bucket, &src.key).await?;
+    delete_keys(client, bucket, &keys).await
+}
+
+/// Delete keys in batches with DeleteObjects
+pub async fn delete_keys(client: Client, bucket: &str, keys: &[String]) -> Result<(), UtilsError> {
+    for batch in keys.chunks(MAX_DELETE_KEYS) {
+        let objects = batch
+            .iter()
+            .map(|key| ObjectIdentifier::builder().key(key).build())
+            .collect::<Result<Vec<_>, _>>()?;
+        let delete = Delete::builder()
+            .set_objects(Some(objects))
+            .quiet(true)
+            .build()?;
+
+        let res = client
+            .delete_objects()
+            .bucket(bucket)
+            .delete(delete)
+            .send()
+            .await
+            .with_context(|| ErrorContext::new("DeleteObjects").bucket(bucket))?;
+
+        if let Some(err) = res.errors().first() {
+            let err = eyre!("Failed deleting {} keys, first: {:?}", res.errors().len(), err.key());
+            return Err(UtilsError::UnexpectedError(err));
+        }
+    }
+
+    Ok(())
+}
+
+fn trash_path(bucket: &str, trash_prefix: &str, date: &str) -> S3Path {
+    S3Path::new(bucket, trash_prefix).join(date)
+}
+
+/// Get current date as YYYY-MM-DD
+fn today() -> Result<String, UtilsError> {
+    let now = DateTime::from(SystemTime::now())
+        .fmt(Format::DateTime)
+        .map_err(|e| UtilsError::UnexpectedError(e.into()))?;
+
+    Ok(now[..10].to_string())
+}
+
+/// Upload local file to AWS S3 with TransferManager, check size and then delete local file,
+/// local file is kept if upload fails
+pub async fn move_to_s3(client: Client, local_path: impl AsRef<Path>, s3path: &S3Path) -> Result<(), UtilsError> {
+    let local_path = local_path.as_ref();
+    let file_size = fs::metadata(local_path).await?.len();
+    TransferManager::new(client.clone()).upload(local_path, s3path).await?;
+
+    let data = head_object(client, &s3path.bucket, &s3path.key).await?;
+    let data_length = data.and_then(|data| data.content_length()).unwrap_or(0) as u64;
+    if file_size != data_length {
+        return Err(UtilsError::SizeMismatch { expected: file_size, actual: data_length });
+    }
+
+    fs::remove_file(local_path).await?;
+
+    Ok(())
+}
+
+/// Download object from AWS S3 to local file with TransferManager, check size and then delete object,
+/// download and delete are pinned to the ETag of object so that an object overwritten meanwhile is kept
+pub async fn move_to_local(client: Client, s3path: &S3Path, local_path: impl AsRef<Path>) -> Result<(), UtilsError> {
+    let local_path = local_path.as_ref();
+    let report = TransferManager::new(client.clone()).download(s3path, local_path).await?;
+    let e_tag = report.e_tag.ok_or_else(|| UtilsError::MissingETag(s3path.uri()))?;
+
+    let file_size = fs::metadata(local_path).await?.len();
+    if file_size != report.bytes {
+        return Err(UtilsError::SizeMismatch { expected: report.bytes, actual: file_size });
+    }
+
+    client
+        .delete_object()
+        .bucket(&s3path.bucket)
+        .key(&s3path.key)
+        .if_match(e_tag)
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("DeleteObject", &s3path.bucket, &s3path.key))?;
+
+    Ok(())
+}
+
+/// Upload all files in local directory passing filters (matched against path relative to directory)
+/// to AWS S3 prefix in parallel, preserving relative paths
+pub async fn upload_dir(
+    client: Client,
+    local_dir: impl AsRef<Path>,
+    s3path: &S3Path,
+    filters: Option<&Filters>,
+    symlinks: SymlinkPolicy,
+    reporter: Option<Arc<dyn ProgressReporter>>,
+) -> Result<(), UtilsError> {
+    let mut files = list_local_files(local_dir.as_ref(), symlinks).await?;
+    if let Some(filters) = filters {
+        files.retain(|file| filters.is_included(&file.relative));
+    }
+
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENCY));
+    let mut tasks = JoinSet::new();
+    for file in files {
+        let semaphore = semaphore.clone();
+        let client = client.clone();
+        let dst = s3path.join(&file.relative);
+        let reporter = reporter.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire().await?;
+            notify(reporter.as_deref(), |reporter| reporter.on_start(&file.relative, file.size));
+            let res = upload_local_file(client, &file, &dst).await;
+            notify(reporter.as_deref(), |reporter| {
+                if res.is_ok() {
+                    reporter.on_bytes(&file.relative, file.size);
+                }
+                reporter.on_finish(&file.relative, res.is_ok());
+            });
+            res
+        });
+    }
+
+    while let Some(res) = tasks.join_next().await {
+        res??;
+    }
+
+    Ok(())
+}
+
+/// Download all objects under AWS S3 prefix passing filters (matched against key relative to prefix)
+/// to local directory in parallel, preserving relative paths
+pub async fn download_dir(
+    client: Client,
+    s3path: &S3Path,
+    local_dir: impl AsRef<Path>,
+    filters: Option<&Filters>,
+    reporter: Option<Arc<dyn ProgressReporter>>,
+) -> Result<(), UtilsError> {
+    let prefix = s3path.join("");
+    let mut files = list_objects_info(client.clone(), &prefix.bucket, &prefix.key, &ListOptions::default()).await?;
+    if let Some(filters) = filters {
+        files.retain(|file| filters.is_included(file.key.strip_prefix(&prefix.key).unwrap_or(&file.key)));
+    }
+
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENCY));
+    let mut tasks = JoinSet::new();
+    for file in files {
+        let semaphore = semaphore.clone();
+        let client = client.clone();
+        let relative = file.key.strip_prefix(&prefix.key).unwrap_or(&file.key).to_string();
+        let path = local_path(local_dir.as_ref(), &relative)?;
+        let src = S3Path::new(prefix.bucket.clone(), file.key);
+        let size = file.size as u64;
+        let reporter = reporter.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire().await?;
+            notify(reporter.as_deref(), |reporter| reporter.on_start(&relative, size));
+            let res = download_to_local_file(client, &src, &path, None).await;
+            notify(reporter.as_deref(), |reporter| {
+                if res.is_ok() {
+                    reporter.on_bytes(&relative, size);
+                }
+                reporter.on_finish(&relative, res.is_ok());
+            });
+            res
+        });
+    }
+
+    while let Some(res) = tasks.join_next().await {
+        res??;
+    }
+
+    Ok(())
+}
+
+fn notify(reporter: Option<&dyn ProgressReporter>, f: impl FnOnce(&dyn ProgressReporter)) {
+    if let Some(reporter) = reporter {
+        f(reporter);
+    }
+}
+
+/// Local file found by walking directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LocalFile {
+    pub path: PathBuf,
+    /// Path relative to walked directory with / separators
+    pub relative: String,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// How symlinks are handled when walking local directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Walk linked directories and read linked files, directory cycles are walked once.
+    /// Links may point outside of walked directory, so following them must be opted in
+    Follow,
+    #[default]
+    Skip,
+    /// Fail on first symlink
+    Error,
+}
+
+/// Walk directory recursively and get all regular files handling symlinks with policy
+pub(crate) async fn list_local_files(dir: &Path, symlinks: SymlinkPolicy) -> Result<Vec<LocalFile>, UtilsError> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    let mut visited = HashSet::from([fs::canonicalize(dir).await?]);
+    while let Some(current) = dirs.pop() {
+        let mut entries = fs::read_dir(&current).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let mut metadata = fs::symlink_metadata(&path).await?;
+            if metadata.is_symlink() {
+                match symlinks {
+                    SymlinkPolicy::Follow => metadata = fs::metadata(&path).await?,
+                    SymlinkPolicy::Skip => continue,
+                    SymlinkPolicy::Error => {
+                        let err = eyre!("Symlink not allowed: {:?}", path);
+                        return Err(UtilsError::UnexpectedError(err));
+                    }
+                }
+            }
+            if metadata.is_dir() {
+                if visited.insert(fs::canonicalize(&path).await?) {
+                    dirs.push(path);
+                }
+            } else if metadata.is_file() {
+                let relative = path
+                    .strip_prefix(dir)
+                    .map_err(|e| UtilsError::UnexpectedError(e.into()))?
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.push(LocalFile {
+                    path,
+                    relative,
+                    size: metadata.len(),
+                    modified: metadata.modified()?,
+                });
+            }
+        }
+    }
+    files.sort_by(|a, b| a.relative.cmp(&b.relative));
+
+    Ok(files)
+}
+
+/// Upload with single PutObject or with multipart upload for files larger than CHUNK_SIZE
+pub(crate) async fn upload_local_file(client: Client, file: &LocalFile, dst: &S3Path) -> Result<(), UtilsError> {
+    if file.size > CHUNK_SIZE {
+        upload_object_multipart_with_options(client, &dst.bucket, &file.path, &dst.key, &PutOptions::new().file_size(file.size))
+            .await
+            .map(|_| ())
+    } else {
+        upload_file(client, &dst.bucket, &file.path, &dst.key).await
+    }
+}
+
+/// Download object creating parent directories and set file modification time if given
+pub(crate) async fn download_to_local_file(client: Client, src: &S3Path, path: &Path, modified: Option<SystemTime>) -> Result<(), UtilsError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    download_file(client, &src.bucket, &src.key, path).await?;
+
+    if let Some(modified) = modified {
+        let file = fs::File::options().write(true).open(path).await?.into_std().await;
+        file.set_modified(modified)?;
+    }
+
+    Ok(())
+}
+
+/// Get local path for path relative to directory, rejecting paths escaping directory
+pub(crate) fn local_path(dir: &Path, relative: &str) -> Result<PathBuf, UtilsError> {
+    let relative = Path::new(relative);
+    if relative.components().any(|component| !matches!(component, Component::Normal(_))) {
+        let err = eyre!("Bad relative path: {:?}", relative);
+        return Err(UtilsError::UnexpectedError(err));
+    }
+
+    Ok(dir.join(relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn s3_path_from_uri() {
+        assert_eq!(S3Path::from_uri("s3://bucket/dir/file.csv").unwrap(), S3Path::new("bucket", "dir/file.csv"));
+        assert_eq!(S3Path::from_uri("s3://bucket").unwrap(), S3Path::new("bucket", ""));
+        assert_eq!(S3Path::from_uri("s3://bucket/").unwrap(), S3Path::new("bucket", ""));
+        assert!(S3Path::from_uri("s3:///key").is_err());
+        assert!(S3Path::from_uri("https://bucket/key").is_err());
+
+        let path: S3Path = "s3://bucket/key".parse().unwrap();
+        assert_eq!(path.uri(), "s3://bucket/key");
+    }
+
+    #[test]
+    fn s3_path_from_url() {
+        let path = S3Path::from_url("https://my.bucket.s3.eu-west-1.amazonaws.com/dir/file%20name.csv").unwrap();
+        assert_eq!(path, S3Path::new("my.bucket", "dir/file name.csv"));
+        let path = S3Path::from_url("https://logs.s3backup.s3.amazonaws.com/key").unwrap();
+        assert_eq!(path, S3Path::new("logs.s3backup", "key"));
+        let path = S3Path::from_url("https://s3.eu-west-1.amazonaws.com/bucket/dir/key").unwrap();
+        assert_eq!(path, S3Path::new("bucket", "dir/key"));
+        let path = S3Path::from_url("https://s3-eu-west-1.amazonaws.com/bucket").unwrap();
+        assert_eq!(path, S3Path::new("bucket", ""));
+        assert_eq!(S3Path::from_url("s3://bucket/key").unwrap(), S3Path::new("bucket", "key"));
+
+        assert!(S3Path::from_url("https://example.com/bucket/key").is_err());
+        assert!(S3Path::from_url("https://s3.amazonaws.com/").is_err());
+        assert!(S3Path::from_url("not a url").is_err());
+    }
+
+    #[test]
+    fn s3_path_https_url_round_trip() {
+        let path = S3Path::new("bucket", "dir/file name.csv");
+        let url = path.https_url("us-east-1");
+        assert_eq!(url, "https://bucket.s3.us-east-1.amazonaws.com/dir/file%20name.csv");
+        assert_eq!(S3Path::from_url(&url).unwrap(), path);
+    }
+
+    #[test]
+    fn s3_path_join() {
+        assert_eq!(S3Path::new("bucket", "dir").join("file").key, "dir/file");
+        assert_eq!(S3Path::new("bucket", "dir/").join("/file").key, "dir/file");
+        assert_eq!(S3Path::new("bucket", "").join("file").key, "file");
+    }
+
+    #[test]
+    fn local_path_rejects_escaping_paths() {
+        let dir = Path::new("/data");
+        assert_eq!(local_path(dir, "a/b.txt").unwrap(), Path::new("/data/a/b.txt"));
+        assert!(local_path(dir, "../etc/passwd").is_err());
+        assert!(local_path(dir, "/etc/passwd").is_err());
+        assert!(local_path(dir, "a/./b").is_ok());
+    }
+}
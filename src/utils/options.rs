@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+/// Options of GetObject request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GetOptions {
+    pub version_id: Option<String>,
+    /// Inclusive byte range (first, last)
+    pub range: Option<(u64, u64)>,
+    /// Fail unless object ETag matches
+    pub if_match: Option<String>,
+}
+
+impl GetOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn version_id(mut self, version_id: impl Into<String>) -> Self {
+        self.version_id = Some(version_id.into());
+        self
+    }
+
+    pub fn range(mut self, first: u64, last: u64) -> Self {
+        self.range = Some((first, last));
+        self
+    }
+
+    pub fn if_match(mut self, e_tag: impl Into<String>) -> Self {
+        self.if_match = Some(e_tag.into());
+        self
+    }
+}
+
+/// Options of upload
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PutOptions {
+    /// File size if already known, otherwise read from file
+    pub file_size: Option<u64>,
+    /// Part size of multipart upload, CHUNK_SIZE if not set
+    pub chunk_size: Option<u64>,
+    /// Max number of parts, MAX_CHUNKS if not set
+    pub max_chunks: Option<u64>,
+    pub content_type: Option<String>,
+    pub storage_class: Option<String>,
+    pub metadata: HashMap<String, String>,
+    /// Also write <key>.sha256 sidecar object with SHA-256 of file
+    pub sidecar_sha256: bool,
+}
+
+impl PutOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn file_size(mut self, file_size: u64) -> Self {
+        self.file_size = Some(file_size);
+        self
+    }
+
+    pub fn chunk_size(mut self, chunk_size: u64) -> Self {
+        self.chunk_size = Some(chunk_size);
+        self
+    }
+
+    pub fn max_chunks(mut self, max_chunks: u64) -> Self {
+        self.max_chunks = Some(max_chunks);
+        self
+    }
+
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    pub fn storage_class(mut self, storage_class: impl Into<String>) -> Self {
+        self.storage_class = Some(storage_class.into());
+        self
+    }
+
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn sidecar_sha256(mut self, sidecar_sha256: bool) -> Self {
+        self.sidecar_sha256 = sidecar_sha256;
+        self
+    }
+}
+
+/// Options of object listing
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListOptions {
+    /// Resolve content types of keys without extension with HeadObject requests
+    pub resolve_content_types: bool,
+    /// List keys after this key
+    pub start_after: Option<String>,
+    /// Max number of listed objects
+    pub max_keys: Option<usize>,
+}
+
+impl ListOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn resolve_content_types(mut self, resolve_content_types: bool) -> Self {
+        self.resolve_content_types = resolve_content_types;
+        self
+    }
+
+    pub fn start_after(mut self, key: impl Into<String>) -> Self {
+        self.start_after = Some(key.into());
+        self
+    }
+
+    pub fn max_keys(mut self, max_keys: usize) -> Self {
+        self.max_keys = Some(max_keys);
+        self
+    }
+}
+
+/// Options of server-side copy, metadata and content type of source are kept unless set
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CopyOptions {
+    /// Version of source object
+    pub version_id: Option<String>,
+    pub content_type: Option<String>,
+    pub storage_class: Option<String>,
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+impl CopyOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn version_id(mut self, version_id: impl Into<String>) -> Self {
+        self.version_id = Some(version_id.into());
+        self
+    }
+
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    pub fn storage_class(mut self, storage_class: impl Into<String>) -> Self {
+        self.storage_class = Some(storage_class.into());
+        self
+    }
+
+    pub fn metadata(mut self, metadata: HashMap<String, String>) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+}
+
+/// Options of AWS Client, unset values come from environment and AWS config files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientOptions {
+    pub region: Option<String>,
+    /// Profile of AWS config and credentials files
+    pub profile: Option<String>,
+    /// Endpoint of S3-compatible storage like MinIO or LocalStack
+    pub endpoint_url: Option<String>,
+    /// Address buckets as https://endpoint/bucket instead of https://bucket.endpoint
+    pub force_path_style: bool,
+}
+
+impl ClientOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    pub fn profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    pub fn endpoint_url(mut self, endpoint_url: impl Into<String>) -> Self {
+        self.endpoint_url = Some(endpoint_url.into());
+        self
+    }
+
+    pub fn force_path_style(mut self, force_path_style: bool) -> Self {
+        self.force_path_style = force_path_style;
+        self
+    }
+}
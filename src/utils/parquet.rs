@@ -0,0 +1,90 @@
+use std::{ops::Range, sync::Arc};
+
+use arrow_array::RecordBatch;
+use aws_sdk_s3::Client;
+use bytes::Bytes;
+use color_eyre::eyre::eyre;
+use futures_util::{future::BoxFuture, FutureExt, TryStreamExt};
+use parquet::{
+    arrow::{
+        arrow_reader::ArrowReaderOptions,
+        async_reader::{AsyncFileReader, ParquetRecordBatchStreamBuilder},
+        ProjectionMask,
+    },
+    errors::ParquetError,
+    file::metadata::{ParquetMetaData, ParquetMetaDataReader},
+};
+
+use crate::utils::{head_object, read_range, S3Path};
+use crate::error::UtilsError;
+
+/// Parquet file in AWS S3 read by ranged requests: footer first, then only needed column chunks
+#[derive(Debug, Clone)]
+pub struct S3ParquetReader {
+    client: Client,
+    path: S3Path,
+    size: u64,
+}
+
+impl S3ParquetReader {
+    /// Get reader of existing object, size is taken by HeadObject request
+    pub async fn new(client: Client, path: &S3Path) -> Result<Self, UtilsError> {
+        let head = head_object(client.clone(), &path.bucket, &path.key)
+            .await?
+            .ok_or_else(|| UtilsError::UnexpectedError(eyre!("Object not found: {}", path)))?;
+
+        Ok(Self::with_size(client, path.clone(), head.content_length().unwrap_or(0) as u64))
+    }
+
+    /// Get reader of object with known size
+    pub fn with_size(client: Client, path: S3Path, size: u64) -> Self {
+        Self { client, path, size }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl AsyncFileReader for S3ParquetReader {
+    fn get_bytes(&mut self, range: Range<u64>) -> BoxFuture<'_, parquet::errors::Result<Bytes>> {
+        async move {
+            read_range(self.client.clone(), &self.path.bucket, &self.path.key, range)
+                .await
+                .map(Bytes::from)
+                .map_err(|err| ParquetError::External(Box::new(err)))
+        }
+        .boxed()
+    }
+
+    fn get_metadata<'a>(&'a mut self, options: Option<&'a ArrowReaderOptions>) -> BoxFuture<'a, parquet::errors::Result<Arc<ParquetMetaData>>> {
+        async move {
+            let size = self.size;
+            let metadata = ParquetMetaDataReader::new()
+                .with_arrow_reader_options(options)
+                .load_and_finish(self, size)
+                .await?;
+
+            Ok(Arc::new(metadata))
+        }
+        .boxed()
+    }
+}
+
+/// Read all columns of parquet file
+pub async fn read_parquet(client: Client, path: &S3Path) -> Result<Vec<RecordBatch>, UtilsError> {
+    let reader = S3ParquetReader::new(client, path).await?;
+    let stream = ParquetRecordBatchStreamBuilder::new(reader).await?.build()?;
+
+    Ok(stream.try_collect().await?)
+}
+
+/// Read only given columns of parquet file, chunks of other columns are not downloaded
+pub async fn read_parquet_columns(client: Client, path: &S3Path, columns: &[&str]) -> Result<Vec<RecordBatch>, UtilsError> {
+    let reader = S3ParquetReader::new(client, path).await?;
+    let builder = ParquetRecordBatchStreamBuilder::new(reader).await?;
+    let mask = ProjectionMask::columns(builder.parquet_schema(), columns.iter().copied());
+    let stream = builder.with_projection(mask).build()?;
+
+    Ok(stream.try_collect().await?)
+}
@@ -0,0 +1,154 @@
+use std::fmt;
+
+/// Receiver of transfer progress used by TransferManager, sync, upload_dir and download_dir, name is object key
+/// or path relative to source directory or prefix, all methods do nothing by default
+pub trait ProgressReporter: fmt::Debug + Send + Sync {
+    /// Transfer of total_bytes started
+    fn on_start(&self, _name: &str, _total_bytes: u64) {}
+
+    /// Bytes were transferred
+    fn on_bytes(&self, _name: &str, _bytes: u64) {}
+
+    /// Part of multipart upload or ranged download finished
+    fn on_part_done(&self, _name: &str, _part_number: u32) {}
+
+    /// Transfer finished successfully or failed
+    fn on_finish(&self, _name: &str, _success: bool) {}
+}
+
+#[cfg(feature = "indicatif")]
+pub use indicatif_reporter::IndicatifReporter;
+#[cfg(feature = "progress-bars")]
+pub use indicatif_reporter::MultiProgressReporter;
+
+#[cfg(feature = "indicatif")]
+mod indicatif_reporter {
+    #[cfg(feature = "progress-bars")]
+    use std::{collections::HashMap, sync::Mutex};
+
+    #[cfg(feature = "progress-bars")]
+    use indicatif::MultiProgress;
+    use indicatif::{ProgressBar, ProgressStyle};
+
+    use super::ProgressReporter;
+
+    const BAR_TEMPLATE: &str = "{msg} [{bar:40}] {bytes}/{total_bytes} {bytes_per_sec} {eta}";
+
+    /// Progress bar of transferred bytes, total grows as transfers start
+    #[derive(Debug, Clone)]
+    pub struct IndicatifReporter {
+        bar: ProgressBar,
+    }
+
+    impl IndicatifReporter {
+        pub fn new() -> Self {
+            let bar = ProgressBar::new(0);
+            set_style(&bar);
+            Self { bar }
+        }
+
+        pub fn bar(&self) -> &ProgressBar {
+            &self.bar
+        }
+    }
+
+    impl Default for IndicatifReporter {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl ProgressReporter for IndicatifReporter {
+        fn on_start(&self, name: &str, total_bytes: u64) {
+            self.bar.inc_length(total_bytes);
+            self.bar.set_message(name.to_string());
+        }
+
+        fn on_bytes(&self, _name: &str, bytes: u64) {
+            self.bar.inc(bytes);
+        }
+
+        fn on_finish(&self, name: &str, success: bool) {
+            if !success {
+                self.bar.println(format!("failed: {}", name));
+            }
+        }
+    }
+
+    /// Progress bars of batch transfer: one bar per running file and aggregate bar of all files,
+    /// bars of finished files are removed
+    #[cfg(feature = "progress-bars")]
+    #[derive(Debug)]
+    pub struct MultiProgressReporter {
+        multi: MultiProgress,
+        total: ProgressBar,
+        bars: Mutex<HashMap<String, ProgressBar>>,
+    }
+
+    #[cfg(feature = "progress-bars")]
+    impl MultiProgressReporter {
+        pub fn new() -> Self {
+            let multi = MultiProgress::new();
+            let total = multi.add(ProgressBar::new(0));
+            set_style(&total);
+            total.set_message("total");
+            Self {
+                multi,
+                total,
+                bars: Mutex::default(),
+            }
+        }
+
+        pub fn multi(&self) -> &MultiProgress {
+            &self.multi
+        }
+
+        /// Aggregate bar of all files
+        pub fn total(&self) -> &ProgressBar {
+            &self.total
+        }
+    }
+
+    #[cfg(feature = "progress-bars")]
+    impl Default for MultiProgressReporter {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    #[cfg(feature = "progress-bars")]
+    impl ProgressReporter for MultiProgressReporter {
+        fn on_start(&self, name: &str, total_bytes: u64) {
+            let bar = self.multi.insert_before(&self.total, ProgressBar::new(total_bytes));
+            set_style(&bar);
+            bar.set_message(name.to_string());
+            self.total.inc_length(total_bytes);
+            if let Ok(mut bars) = self.bars.lock() {
+                bars.insert(name.to_string(), bar);
+            }
+        }
+
+        fn on_bytes(&self, name: &str, bytes: u64) {
+            if let Some(bar) = self.bars.lock().ok().and_then(|bars| bars.get(name).cloned()) {
+                bar.inc(bytes);
+            }
+            self.total.inc(bytes);
+        }
+
+        fn on_finish(&self, name: &str, success: bool) {
+            if let Some(bar) = self.bars.lock().ok().and_then(|mut bars| bars.remove(name)) {
+                bar.finish_and_clear();
+                self.multi.remove(&bar);
+            }
+            if !success {
+                let _ = self.multi.println(format!("failed: {}", name));
+            }
+        }
+    }
+
+    fn set_style(bar: &ProgressBar) {
+        if let Ok(style) = ProgressStyle::with_template(BAR_TEMPLATE) {
+            bar.set_style(style.progress_chars("=> "));
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+
+use aws_sdk_s3::Client;
+use aws_smithy_types::date_time::Format;
+use bytes::Bytes;
+use futures_util::stream::{self, BoxStream, StreamExt};
+
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+/// Object body as stream with headers needed to send it as HTTP response
+pub struct HttpObject {
+    pub body: BoxStream<'static, Result<Bytes, UtilsError>>,
+    pub content_type: Option<String>,
+    pub content_length: Option<u64>,
+    /// Content-Range of partial content
+    pub content_range: Option<String>,
+    pub e_tag: Option<String>,
+    /// Last-Modified in HTTP date format
+    pub last_modified: Option<String>,
+}
+
+impl HttpObject {
+    /// Get HTTP status code of response: 206 for partial content, 200 otherwise
+    pub fn status(&self) -> u16 {
+        match self.content_range {
+            Some(_) => 206,
+            None => 200,
+        }
+    }
+}
+
+impl fmt::Debug for HttpObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpObject")
+            .field("content_type", &self.content_type)
+            .field("content_length", &self.content_length)
+            .field("content_range", &self.content_range)
+            .field("e_tag", &self.e_tag)
+            .field("last_modified", &self.last_modified)
+            .finish()
+    }
+}
+
+/// Get object body as stream of chunks for HTTP response without buffering,
+/// range is Range header of client request passed through to AWS S3
+pub async fn stream_to_http_body(client: Client, bucket: &str, key: &str, range: Option<&str>) -> Result<HttpObject, UtilsError> {
+    let res = client
+        .get_object()
+        .bucket(bucket)
+        .key(key)
+        .set_range(range.map(|val| val.to_string()))
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("GetObject", bucket, key))?;
+
+    let last_modified = res.last_modified().and_then(|val| val.fmt(Format::HttpDate).ok());
+    let body = stream::unfold(Some(res.body), |body| async move {
+        let mut body = body?;
+        match body.try_next().await {
+            Ok(Some(bytes)) => Some((Ok(bytes), Some(body))),
+            Ok(None) => None,
+            Err(err) => Some((Err(err.into()), None)),
+        }
+    });
+
+    Ok(HttpObject {
+        body: body.boxed(),
+        content_type: res.content_type,
+        content_length: res.content_length.map(|val| val as u64),
+        content_range: res.content_range,
+        e_tag: res.e_tag,
+        last_modified,
+    })
+}
@@ -0,0 +1,73 @@
+use std::{sync::{atomic::{AtomicU32, AtomicU64, Ordering}, Arc}, time::Duration};
+
+use aws_sdk_s3::{
+    config::{interceptors::{BeforeSerializationInterceptorContextRef, BeforeTransmitInterceptorContextRef}, ConfigBag, Intercept, RuntimeComponents},
+    error::BoxError,
+};
+
+/// Result of upload or download
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransferReport {
+    pub bytes: u64,
+    /// Number of parts or ranges, 1 for single request transfer
+    pub parts: u32,
+    /// Number of retried requests attempts
+    pub retries: u32,
+    pub duration: Duration,
+    pub e_tag: Option<String>,
+}
+
+impl TransferReport {
+    /// Effective throughput in bytes per second
+    pub fn throughput(&self) -> f64 {
+        match self.duration.as_secs_f64() {
+            secs if secs > 0.0 => self.bytes as f64 / secs,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Interceptor counting request attempts beyond the first, shared by all requests of one transfer,
+/// also counts bytes of finished parts for partial progress
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RetryCounter {
+    executions: Arc<AtomicU32>,
+    attempts: Arc<AtomicU32>,
+    bytes: Arc<AtomicU64>,
+}
+
+impl RetryCounter {
+    pub(crate) fn retries(&self) -> u32 {
+        let attempts = self.attempts.load(Ordering::Relaxed);
+        attempts.saturating_sub(self.executions.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn add_bytes(&self, bytes: u64) {
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+}
+
+impl Intercept for RetryCounter {
+    fn name(&self) -> &'static str {
+        "RetryCounter"
+    }
+
+    fn read_before_execution(&self, _context: &BeforeSerializationInterceptorContextRef<'_>, _cfg: &mut ConfigBag) -> Result<(), BoxError> {
+        self.executions.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn read_before_attempt(
+        &self,
+        _context: &BeforeTransmitInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
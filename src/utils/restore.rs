@@ -0,0 +1,162 @@
+use std::{collections::{HashMap, HashSet}, sync::Arc, time::Duration};
+
+use aws_sdk_s3::{error::ProvideErrorMetadata, types::{GlacierJobParameters, RestoreRequest, Tier}, Client};
+use color_eyre::eyre::eyre;
+use tokio::{sync::Semaphore, task::JoinSet};
+
+use crate::utils::{head_object, list_objects_info, ListOptions, MAX_CONCURRENCY};
+use crate::error::{ErrorContext, UtilsError};
+
+/// Storage classes which objects must be restored before they can be read
+const ARCHIVE_STORAGE_CLASSES: [&str; 2] = ["GLACIER", "DEEP_ARCHIVE"];
+
+/// Retrieval tier of Glacier restore, Expedited is not available for DEEP_ARCHIVE
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RestoreTier {
+    Expedited,
+    #[default]
+    Standard,
+    Bulk,
+}
+
+impl From<RestoreTier> for Tier {
+    fn from(value: RestoreTier) -> Self {
+        match value {
+            RestoreTier::Expedited => Tier::Expedited,
+            RestoreTier::Standard => Tier::Standard,
+            RestoreTier::Bulk => Tier::Bulk,
+        }
+    }
+}
+
+/// Restore state of archived object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreStatus {
+    /// Object is not archived or restore was never requested
+    NotRequested,
+    InProgress,
+    /// Temporary copy is readable
+    Restored,
+}
+
+/// Request restore of archived object for days, restore already in progress is not an error
+pub async fn restore_object(client: Client, bucket: &str, key: &str, days: i32, tier: RestoreTier) -> Result<(), UtilsError> {
+    let job_parameters = GlacierJobParameters::builder()
+        .tier(tier.into())
+        .build()?;
+    let restore_request = RestoreRequest::builder()
+        .days(days)
+        .glacier_job_parameters(job_parameters)
+        .build();
+
+    let res = client
+        .restore_object()
+        .bucket(bucket)
+        .key(key)
+        .restore_request(restore_request)
+        .send()
+        .await;
+
+    match res {
+        Ok(_) => Ok(()),
+        Err(sdk_err) => match sdk_err.as_service_error().and_then(|err| err.code()) {
+            Some("RestoreAlreadyInProgress") => Ok(()),
+            _ => Err(UtilsError::from(sdk_err).context(ErrorContext::object("RestoreObject", bucket, key))),
+        }
+    }
+}
+
+/// Request restore of all GLACIER and DEEP_ARCHIVE objects under prefix for days
+/// with at most MAX_CONCURRENCY RestoreObject requests in flight, errors are reported per key
+pub async fn restore_prefix(
+    client: Client,
+    bucket: &str,
+    prefix: &str,
+    days: i32,
+    tier: RestoreTier,
+) -> Result<HashMap<String, Result<(), UtilsError>>, UtilsError> {
+    let files = list_objects_info(client.clone(), bucket, prefix, &ListOptions::default()).await?;
+    let keys = files
+        .into_iter()
+        .filter(|file| file.storage_class.as_deref().is_some_and(|val| ARCHIVE_STORAGE_CLASSES.contains(&val)))
+        .map(|file| file.key);
+
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENCY));
+    let mut tasks = JoinSet::new();
+    for key in keys {
+        let client = client.clone();
+        let bucket = bucket.to_string();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let res = match semaphore.acquire().await {
+                Ok(_permit) => restore_object(client, &bucket, &key, days, tier).await,
+                Err(err) => Err(err.into()),
+            };
+            (key, res)
+        });
+    }
+
+    let mut res = HashMap::new();
+    while let Some(task) = tasks.join_next().await {
+        let (key, restored) = task?;
+        res.insert(key, restored);
+    }
+
+    Ok(res)
+}
+
+/// Get restore state of object from x-amz-restore header, ObjectNotFound error if object doesn't exist
+pub async fn restore_status(client: Client, bucket: &str, key: &str) -> Result<RestoreStatus, UtilsError> {
+    let res = head_object(client, bucket, key)
+        .await?
+        .ok_or_else(|| UtilsError::ObjectNotFound(format!("s3://{}/{}", bucket, key)))?;
+
+    // e.g. ongoing-request="false", expiry-date="Fri, 21 Dec 2012 00:00:00 GMT"
+    let status = match res.restore() {
+        None => RestoreStatus::NotRequested,
+        Some(val) if val.contains("ongoing-request=\"true\"") => RestoreStatus::InProgress,
+        Some(_) => RestoreStatus::Restored,
+    };
+
+    Ok(status)
+}
+
+/// Poll restore state of keys every poll_interval until all of them are restored,
+/// fails if restore of any key was never requested
+pub async fn wait_for_restore(client: Client, bucket: &str, keys: &[String], poll_interval: Duration) -> Result<(), UtilsError> {
+    let mut pending: HashSet<String> = keys.iter().cloned().collect();
+    loop {
+        let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENCY));
+        let mut tasks = JoinSet::new();
+        for key in pending.iter().cloned() {
+            let client = client.clone();
+            let bucket = bucket.to_string();
+            let semaphore = semaphore.clone();
+            tasks.spawn(async move {
+                let res = match semaphore.acquire().await {
+                    Ok(_permit) => restore_status(client, &bucket, &key).await,
+                    Err(err) => Err(err.into()),
+                };
+                (key, res)
+            });
+        }
+
+        while let Some(task) = tasks.join_next().await {
+            let (key, status) = task?;
+            match status? {
+                RestoreStatus::Restored => {
+                    pending.remove(&key);
+                }
+                RestoreStatus::InProgress => {}
+                RestoreStatus::NotRequested => {
+                    return Err(UtilsError::UnexpectedError(eyre!("restore of s3://{}/{} was not requested", bucket, key)));
+                }
+            }
+        }
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
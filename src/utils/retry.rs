@@ -0,0 +1,139 @@
+use std::{future::Future, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use tracing::debug;
+
+use crate::error::UtilsError;
+
+/// Error codes of AWS S3 service errors worth retrying
+pub(crate) const RETRYABLE_CODES: &[&str] = &["SlowDown", "Throttling", "ThrottlingException", "RequestTimeout", "InternalError", "ServiceUnavailable"];
+
+/// Error codes of AWS service errors asking to reduce request rate
+pub(crate) const THROTTLING_CODES: &[&str] = &["SlowDown", "Throttling", "ThrottlingException", "RequestLimitExceeded", "TooManyRequestsException"];
+
+/// Retry policy of with_retry: exponential backoff with optional full jitter
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Max attempts including the first, 1 disables retries
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// No retry is started after this time since the first attempt
+    pub max_elapsed: Option<Duration>,
+    /// Sleep random duration up to backoff instead of whole backoff
+    pub jitter: bool,
+    /// Decides if error is retried
+    pub retryable: fn(&UtilsError) -> bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            max_elapsed: None,
+            jitter: true,
+            retryable: is_retryable_error,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Policy without retries
+    pub fn none() -> Self {
+        Self::default().max_attempts(1)
+    }
+
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    pub fn max_elapsed(mut self, max_elapsed: Duration) -> Self {
+        self.max_elapsed = Some(max_elapsed);
+        self
+    }
+
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn retryable(mut self, retryable: fn(&UtilsError) -> bool) -> Self {
+        self.retryable = retryable;
+        self
+    }
+
+    /// Get sleep before retry after failed attempt (1 based)
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_backoff);
+        if !self.jitter {
+            return backoff;
+        }
+
+        // sub-second clock is random enough to spread retries of parallel requests
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+        backoff.mul_f64(f64::from(nanos % 1000) / 1000.0)
+    }
+}
+
+/// Run op until it succeeds, fails with error not retryable by policy or policy limits are reached
+pub async fn with_retry<T, F, Fut>(policy: &RetryPolicy, mut op: F) -> Result<T, UtilsError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, UtilsError>>,
+{
+    let start = Instant::now();
+    let mut attempt = 1;
+    loop {
+        let err = match op().await {
+            Ok(res) => return Ok(res),
+            Err(err) => err,
+        };
+        let expired = policy.max_elapsed.is_some_and(|max_elapsed| start.elapsed() >= max_elapsed);
+        if attempt >= policy.max_attempts || expired || !(policy.retryable)(&err) {
+            return Err(err);
+        }
+
+        let backoff = policy.backoff(attempt);
+        debug!(attempt, backoff = ?backoff, error = %err, "retrying");
+        tokio::time::sleep(backoff).await;
+        attempt += 1;
+    }
+}
+
+/// Check if error is transient: timeouts, connection and stream failures,
+/// throttling and server errors of requests used by transfers and listing
+pub fn is_retryable_error(err: &UtilsError) -> bool {
+    let retried = matches!(
+        err.root(),
+        UtilsError::IoError(_)
+            | UtilsError::AWSSmithyError(_)
+            | UtilsError::GetObjectError(_)
+            | UtilsError::HeadObjectError(_)
+            | UtilsError::PutObjectError(_)
+            | UtilsError::UploadPartError(_)
+            | UtilsError::UploadPartCopyError(_)
+            | UtilsError::ListObjectsV2Error(_)
+            | UtilsError::CopyObjectError(_)
+            | UtilsError::DeleteObjectsError(_)
+    );
+
+    retried && err.is_retryable()
+}
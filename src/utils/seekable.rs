@@ -0,0 +1,239 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    future::Future,
+    io::{self, SeekFrom},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use aws_sdk_s3::Client;
+use color_eyre::eyre::eyre;
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+use crate::utils::{get_aws_object_with_options, head_object, GetOptions, S3Path, READ_BUF_SIZE};
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+const CACHE_BLOCKS: usize = 16;
+
+type BlockFuture = Pin<Box<dyn Future<Output = Result<Vec<u8>, UtilsError>> + Send>>;
+
+/// AsyncRead + AsyncSeek over AWS S3 object, data is fetched by ranged requests of whole blocks
+/// and least recently read blocks are evicted from cache, so seeking format readers can work on objects directly.
+/// Blocks are fetched with If-Match on ETag, so object replaced while reading fails the read
+pub struct S3SeekableReader {
+    client: Client,
+    path: S3Path,
+    size: u64,
+    e_tag: Option<String>,
+    pos: u64,
+    block_size: u64,
+    cache_blocks: usize,
+    cache: HashMap<u64, Arc<Vec<u8>>>,
+    /// Cached blocks from least recently read
+    order: VecDeque<u64>,
+    pending: Option<(u64, BlockFuture)>,
+}
+
+impl S3SeekableReader {
+    /// Get reader of existing object, size and ETag are taken by HeadObject request
+    pub async fn new(client: Client, path: &S3Path) -> Result<Self, UtilsError> {
+        let head = head_object(client.clone(), &path.bucket, &path.key)
+            .await?
+            .ok_or_else(|| UtilsError::UnexpectedError(eyre!("Object not found: {}", path)))?;
+        let reader = Self::with_size(client, path.clone(), head.content_length().unwrap_or(0) as u64);
+
+        Ok(match head.e_tag() {
+            Some(e_tag) => reader.e_tag(e_tag),
+            None => reader,
+        })
+    }
+
+    /// Get reader of object with known size
+    pub fn with_size(client: Client, path: S3Path, size: u64) -> Self {
+        Self {
+            client,
+            path,
+            size,
+            e_tag: None,
+            pos: 0,
+            block_size: READ_BUF_SIZE as u64,
+            cache_blocks: CACHE_BLOCKS,
+            cache: HashMap::new(),
+            order: VecDeque::new(),
+            pending: None,
+        }
+    }
+
+    /// Size of ranged requests
+    pub fn block_size(mut self, block_size: u64) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    /// ETag every block is fetched with as If-Match
+    pub fn e_tag(mut self, e_tag: impl Into<String>) -> Self {
+        self.e_tag = Some(e_tag.into());
+        self
+    }
+
+    /// Max number of cached blocks
+    pub fn cache_blocks(mut self, cache_blocks: usize) -> Self {
+        self.cache_blocks = cache_blocks.max(1);
+        self
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    fn fetch(&self, block: u64) -> BlockFuture {
+        let start = block * self.block_size;
+        let end = (start + self.block_size).min(self.size);
+        let client = self.client.clone();
+        let path = self.path.clone();
+        let opts = GetOptions {
+            range: Some((start, end.saturating_sub(1))),
+            if_match: self.e_tag.clone(),
+            ..Default::default()
+        };
+        Box::pin(async move {
+            if start >= end {
+                return Ok(Vec::new());
+            }
+            let mut buf = Vec::with_capacity((end - start) as usize);
+            let mut object = get_aws_object_with_options(client, &path.bucket, &path.key, &opts).await?;
+            while let Some(bytes) = object.body.try_next().await.with_context(|| ErrorContext::object("GetObject", &path.bucket, &path.key))? {
+                buf.extend_from_slice(&bytes);
+            }
+
+            Ok(buf)
+        })
+    }
+
+    /// Mark cached block as most recently read
+    fn touch(&mut self, block: u64) {
+        if self.order.back() != Some(&block) {
+            self.order.retain(|cached| *cached != block);
+            self.order.push_back(block);
+        }
+    }
+
+    fn insert(&mut self, block: u64, data: Vec<u8>) {
+        if self.order.len() >= self.cache_blocks {
+            if let Some(oldest) = self.order.pop_front() {
+                self.cache.remove(&oldest);
+            }
+        }
+        self.cache.insert(block, Arc::new(data));
+        self.order.push_back(block);
+    }
+}
+
+impl fmt::Debug for S3SeekableReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3SeekableReader")
+            .field("path", &self.path)
+            .field("size", &self.size)
+            .field("pos", &self.pos)
+            .field("block_size", &self.block_size)
+            .field("cached_blocks", &self.order.len())
+            .finish()
+    }
+}
+
+impl AsyncRead for S3SeekableReader {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.pos >= this.size || buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let block = this.pos / this.block_size;
+        loop {
+            if let Some(data) = this.cache.get(&block).cloned() {
+                this.touch(block);
+                let offset = (this.pos - block * this.block_size) as usize;
+                let len = buf.remaining().min(data.len().saturating_sub(offset));
+                if len == 0 {
+                    let err = eyre!("Object {} is shorter than {} bytes", this.path, this.size);
+                    return Poll::Ready(Err(io::Error::new(io::ErrorKind::UnexpectedEof, err)));
+                }
+                buf.put_slice(&data[offset..offset + len]);
+                this.pos += len as u64;
+                return Poll::Ready(Ok(()));
+            }
+
+            // fetch started by previous poll is reused, fetch of other block is dropped after seek
+            let mut fut = match this.pending.take() {
+                Some((pending, fut)) if pending == block => fut,
+                _ => this.fetch(block),
+            };
+            match fut.as_mut().poll(cx) {
+                Poll::Pending => {
+                    this.pending = Some((block, fut));
+                    return Poll::Pending;
+                }
+                Poll::Ready(Ok(data)) => this.insert(block, data),
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(io::Error::other(err))),
+            }
+        }
+    }
+}
+
+impl AsyncSeek for S3SeekableReader {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        let pos = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => this.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => this.pos.checked_add_signed(offset),
+        };
+        this.pos = pos.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative or overflowing position"))?;
+
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    fn reader() -> S3SeekableReader {
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
+            .region(aws_sdk_s3::config::Region::new("us-east-1"))
+            .build();
+        S3SeekableReader::with_size(Client::from_conf(config), S3Path::new("bucket", "key"), 6)
+            .block_size(2)
+            .cache_blocks(2)
+    }
+
+    #[tokio::test]
+    async fn cache_evicts_least_recently_read_block() {
+        let mut reader = reader();
+        reader.insert(0, b"ab".to_vec());
+        reader.insert(1, b"cd".to_vec());
+
+        // reading block 0 makes block 1 the least recently read one
+        let mut buf = [0; 2];
+        reader.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ab");
+        reader.insert(2, b"ef".to_vec());
+
+        assert!(reader.cache.contains_key(&0));
+        assert!(!reader.cache.contains_key(&1));
+        assert!(reader.cache.contains_key(&2));
+    }
+}
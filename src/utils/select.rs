@@ -0,0 +1,122 @@
+use aws_sdk_s3::{
+    types::{
+        CompressionType, CsvInput, CsvOutput, ExpressionType, FileHeaderInfo, InputSerialization, JsonInput, JsonOutput, JsonType,
+        OutputSerialization, ParquetInput, SelectObjectContentEventStream,
+    },
+    Client,
+};
+use bytes::Bytes;
+use futures_util::stream;
+use tokio_stream::Stream;
+
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+/// Format of object queried by S3 Select
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectInput {
+    /// CSV, columns are referenced by header names if has_header is set
+    Csv { has_header: bool, delimiter: char, gzip: bool },
+    /// JSON document or JSON lines
+    Json { lines: bool, gzip: bool },
+    Parquet,
+}
+
+impl SelectInput {
+    /// CSV with header, comma delimited
+    pub fn csv() -> Self {
+        SelectInput::Csv { has_header: true, delimiter: ',', gzip: false }
+    }
+
+    /// JSON lines
+    pub fn json_lines() -> Self {
+        SelectInput::Json { lines: true, gzip: false }
+    }
+
+    fn serialization(&self) -> InputSerialization {
+        let builder = InputSerialization::builder();
+        match self {
+            SelectInput::Csv { has_header, delimiter, gzip } => {
+                let header = if *has_header { FileHeaderInfo::Use } else { FileHeaderInfo::None };
+                builder
+                    .csv(CsvInput::builder().file_header_info(header).field_delimiter(delimiter.to_string()).build())
+                    .compression_type(compression(*gzip))
+            }
+            SelectInput::Json { lines, gzip } => {
+                let json_type = if *lines { JsonType::Lines } else { JsonType::Document };
+                builder
+                    .json(JsonInput::builder().r#type(json_type).build())
+                    .compression_type(compression(*gzip))
+            }
+            SelectInput::Parquet => builder.parquet(ParquetInput::builder().build()),
+        }
+        .build()
+    }
+}
+
+/// Format of S3 Select results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SelectOutput {
+    Csv,
+    /// One JSON record per line
+    #[default]
+    Json,
+}
+
+impl SelectOutput {
+    fn serialization(&self) -> OutputSerialization {
+        let builder = OutputSerialization::builder();
+        match self {
+            SelectOutput::Csv => builder.csv(CsvOutput::builder().build()),
+            SelectOutput::Json => builder.json(JsonOutput::builder().record_delimiter("\n").build()),
+        }
+        .build()
+    }
+}
+
+/// Run S3 Select sql query on object, stream yields chunks of results as they arrive,
+/// chunks are not aligned to records
+pub async fn select_object_content(
+    client: Client,
+    bucket: &str,
+    key: &str,
+    sql: &str,
+    input_format: &SelectInput,
+    output_format: &SelectOutput,
+) -> Result<impl Stream<Item = Result<Bytes, UtilsError>>, UtilsError> {
+    let res = client
+        .select_object_content()
+        .bucket(bucket)
+        .key(key)
+        .expression(sql)
+        .expression_type(ExpressionType::Sql)
+        .input_serialization(input_format.serialization())
+        .output_serialization(output_format.serialization())
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("SelectObjectContent", bucket, key))?;
+
+    Ok(stream::unfold(Some(res.payload), |payload| async move {
+        let mut payload = payload?;
+        loop {
+            match payload.recv().await {
+                Ok(Some(SelectObjectContentEventStream::Records(records))) => {
+                    let Some(data) = records.payload else {
+                        continue;
+                    };
+                    return Some((Ok(Bytes::from(data.into_inner())), Some(payload)));
+                }
+                Ok(Some(SelectObjectContentEventStream::End(_))) | Ok(None) => return None,
+                Ok(Some(_)) => continue,
+                Err(err) => return Some((Err(err.into()), None)),
+            }
+        }
+    }))
+}
+
+fn compression(gzip: bool) -> CompressionType {
+    if gzip {
+        CompressionType::Gzip
+    } else {
+        CompressionType::None
+    }
+}
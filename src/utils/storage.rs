@@ -0,0 +1,200 @@
+use std::{collections::{BTreeMap, HashMap}, future::Future, sync::{Arc, Mutex, MutexGuard}, time::SystemTime};
+
+use aws_sdk_s3::{
+    config::http::HttpResponse,
+    error::{ErrorMetadata, SdkError},
+    operation::get_object::GetObjectError,
+    primitives::ByteStream,
+    types::error::NoSuchKey,
+    Client,
+};
+use aws_smithy_runtime_api::http::StatusCode;
+use aws_smithy_types::{body::SdkBody, DateTime};
+use color_eyre::eyre::eyre;
+use md5::{Digest, Md5};
+
+use crate::utils::{delete_object, get_object_metadata, list_objects_info, read_file, ListOptions, ObjectInfo, ObjectMetadata};
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+/// Basic object storage operations, implemented by AWS S3 Client and by InMemoryStorage for tests
+pub trait S3Storage: Send + Sync {
+    /// Read whole object, error with NoSuchKey code if key doesn't exist
+    fn read(&self, bucket: &str, key: &str) -> impl Future<Output = Result<Vec<u8>, UtilsError>> + Send;
+
+    /// Create or replace object
+    fn write(&self, bucket: &str, key: &str, data: Vec<u8>) -> impl Future<Output = Result<(), UtilsError>> + Send;
+
+    /// List objects under prefix sorted by key, "directory" placeholders are skipped
+    fn list(&self, bucket: &str, prefix: &str) -> impl Future<Output = Result<Vec<ObjectInfo>, UtilsError>> + Send;
+
+    /// Delete object, deleting missing object is not an error
+    fn delete(&self, bucket: &str, key: &str) -> impl Future<Output = Result<(), UtilsError>> + Send;
+
+    /// Get object metadata, None if key doesn't exist
+    fn head(&self, bucket: &str, key: &str) -> impl Future<Output = Result<Option<ObjectMetadata>, UtilsError>> + Send;
+}
+
+impl S3Storage for Client {
+    async fn read(&self, bucket: &str, key: &str) -> Result<Vec<u8>, UtilsError> {
+        read_file(self.clone(), bucket, key).await
+    }
+
+    async fn write(&self, bucket: &str, key: &str, data: Vec<u8>) -> Result<(), UtilsError> {
+        self.put_object()
+            .bucket(bucket)
+            .key(key)
+            .body(ByteStream::from(data))
+            .send()
+            .await
+            .with_context(|| ErrorContext::object("PutObject", bucket, key))?;
+
+        Ok(())
+    }
+
+    async fn list(&self, bucket: &str, prefix: &str) -> Result<Vec<ObjectInfo>, UtilsError> {
+        list_objects_info(self.clone(), bucket, prefix, &ListOptions::default()).await
+    }
+
+    async fn delete(&self, bucket: &str, key: &str) -> Result<(), UtilsError> {
+        delete_object(self.clone(), bucket, key, None).await
+    }
+
+    async fn head(&self, bucket: &str, key: &str) -> Result<Option<ObjectMetadata>, UtilsError> {
+        get_object_metadata(self.clone(), bucket, key).await
+    }
+}
+
+/// In-memory object storage for tests, clones share the same objects
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryStorage {
+    objects: Arc<Mutex<Objects>>,
+}
+
+/// Objects by (bucket, key)
+type Objects = BTreeMap<(String, String), StoredObject>;
+
+#[derive(Debug, Clone)]
+struct StoredObject {
+    data: Vec<u8>,
+    e_tag: String,
+    last_modified: DateTime,
+}
+
+impl InMemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn objects(&self) -> Result<MutexGuard<'_, Objects>, UtilsError> {
+        self.objects
+            .lock()
+            .map_err(|_| UtilsError::UnexpectedError(eyre!("InMemoryStorage lock poisoned")))
+    }
+}
+
+/// NoSuchKey error like GetObject of missing key returns
+fn no_such_key(bucket: &str, key: &str) -> UtilsError {
+    let message = "The specified key does not exist.";
+    let meta = ErrorMetadata::builder().code("NoSuchKey").message(message).build();
+    let err = GetObjectError::NoSuchKey(NoSuchKey::builder().message(message).meta(meta).build());
+    let status = StatusCode::try_from(404).expect("404 is valid status code");
+
+    UtilsError::from(SdkError::service_error(err, HttpResponse::new(status, SdkBody::empty())))
+        .context(ErrorContext::object("GetObject", bucket, key))
+}
+
+impl S3Storage for InMemoryStorage {
+    async fn read(&self, bucket: &str, key: &str) -> Result<Vec<u8>, UtilsError> {
+        self.objects()?
+            .get(&(bucket.to_string(), key.to_string()))
+            .map(|object| object.data.clone())
+            .ok_or_else(|| no_such_key(bucket, key))
+    }
+
+    async fn write(&self, bucket: &str, key: &str, data: Vec<u8>) -> Result<(), UtilsError> {
+        let object = StoredObject {
+            e_tag: format!("\"{:x}\"", Md5::digest(&data)),
+            last_modified: DateTime::from(SystemTime::now()),
+            data,
+        };
+        self.objects()?.insert((bucket.to_string(), key.to_string()), object);
+
+        Ok(())
+    }
+
+    async fn list(&self, bucket: &str, prefix: &str) -> Result<Vec<ObjectInfo>, UtilsError> {
+        let files = self.objects()?
+            .iter()
+            .filter(|((object_bucket, key), _)| object_bucket == bucket && key.starts_with(prefix) && !key.ends_with('/'))
+            .map(|((_, key), object)| ObjectInfo {
+                key: key.clone(),
+                size: object.data.len() as i64,
+                e_tag: Some(object.e_tag.clone()),
+                last_modified: Some(object.last_modified),
+                storage_class: Some("STANDARD".to_string()),
+                content_type: mime_guess::from_path(key).first_raw().map(|val| val.to_string()),
+            })
+            .collect();
+
+        Ok(files)
+    }
+
+    async fn delete(&self, bucket: &str, key: &str) -> Result<(), UtilsError> {
+        self.objects()?.remove(&(bucket.to_string(), key.to_string()));
+
+        Ok(())
+    }
+
+    async fn head(&self, bucket: &str, key: &str) -> Result<Option<ObjectMetadata>, UtilsError> {
+        let metadata = self.objects()?
+            .get(&(bucket.to_string(), key.to_string()))
+            .map(|object| ObjectMetadata {
+                size: object.data.len() as i64,
+                e_tag: Some(object.e_tag.clone()),
+                content_type: mime_guess::from_path(key).first_raw().map(|val| val.to_string()),
+                last_modified: Some(object.last_modified),
+                storage_class: Some("STANDARD".to_string()),
+                metadata: HashMap::new(),
+            });
+
+        Ok(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn read_missing_key_is_no_such_key() {
+        let storage = InMemoryStorage::new();
+        let err = storage.read("bucket", "missing").await.unwrap_err();
+
+        assert_eq!(err.error_code(), Some("NoSuchKey"));
+        assert_eq!(err.status_code(), Some(404));
+        assert!(!err.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn write_read_list_and_delete() {
+        let storage = InMemoryStorage::new();
+        storage.write("bucket", "dir/", Vec::new()).await.unwrap();
+        storage.write("bucket", "dir/b.txt", b"b".to_vec()).await.unwrap();
+        storage.write("bucket", "dir/a.csv", b"abc".to_vec()).await.unwrap();
+        storage.write("other", "dir/c.txt", b"c".to_vec()).await.unwrap();
+
+        assert_eq!(storage.read("bucket", "dir/a.csv").await.unwrap(), b"abc");
+        let files = storage.list("bucket", "dir/").await.unwrap();
+        let keys: Vec<&str> = files.iter().map(|file| file.key.as_str()).collect();
+        assert_eq!(keys, ["dir/a.csv", "dir/b.txt"]);
+        assert_eq!(files[0].size, 3);
+        assert_eq!(files[0].e_tag.as_deref(), Some("\"900150983cd24fb0d6963f7d28e17f72\""));
+        assert_eq!(files[0].content_type.as_deref(), Some("text/csv"));
+
+        let head = storage.head("bucket", "dir/b.txt").await.unwrap().unwrap();
+        assert_eq!(head.size, 1);
+        storage.delete("bucket", "dir/b.txt").await.unwrap();
+        storage.delete("bucket", "dir/b.txt").await.unwrap();
+        assert!(storage.head("bucket", "dir/b.txt").await.unwrap().is_none());
+    }
+}
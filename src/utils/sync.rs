@@ -0,0 +1,1196 @@
+use std::{collections::{HashMap, HashSet}, error::Error, fmt, future::Future, path::{Path, PathBuf}, pin::Pin, sync::Arc, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use aws_sdk_s3::Client;
+use color_eyre::eyre::eyre;
+use tokio::{fs, sync::{mpsc::UnboundedSender, Semaphore}, task::JoinSet};
+use tracing::{debug, warn};
+
+use crate::utils::{cancellable, cancellable_copy_object, CancellationToken, delete_keys, CopyOptions, etag_matches, head_objects, list_local_files, list_objects_info_parallel, local_path, FileState, Filters, LocalFile, ObjectInfo, ProgressReporter, S3Path, SymlinkPolicy, SyncState, TransferManager, MAX_CONCURRENCY};
+use crate::error::UtilsError;
+
+/// How sync decides if file changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompareStrategy {
+    /// Size differs or source is newer than destination, no data is read
+    #[default]
+    SizeMtime,
+    /// Size or ETag differs, local files are hashed and multipart ETags need a HeadObject request
+    Checksum,
+}
+
+/// How bidirectional sync resolves files changed on both sides since last sync
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Most recently modified side wins, existing file wins over deletion
+    #[default]
+    NewestWins,
+    PreferLocal,
+    PreferRemote,
+    /// Fail before transferring anything
+    Fail,
+}
+
+/// Sync options
+#[derive(Debug, Clone)]
+pub struct SyncOptions {
+    /// Max number of files transferred in parallel
+    pub concurrency: usize,
+    /// Max number of prefixes listed in parallel
+    pub listing_concurrency: usize,
+    /// Max average upload and download bandwidth in bytes per second
+    pub bytes_per_sec: Option<u64>,
+    /// Delete destination files missing in source
+    pub delete_extraneous: bool,
+    /// Only plan, nothing is transferred or deleted
+    pub dry_run: bool,
+    /// Include/exclude rules for relative paths, excluded files are neither transferred nor deleted
+    pub filters: Filters,
+    /// Symlink handling when walking local directory, symlinks are skipped by default
+    pub symlinks: SymlinkPolicy,
+    pub compare: CompareStrategy,
+    /// Receiver of per-file progress events
+    pub progress: Option<UnboundedSender<SyncEvent>>,
+    /// Receiver of per-file progress shared with TransferManager
+    pub reporter: Option<Arc<dyn ProgressReporter>>,
+    /// Callback awaited after each transferred or deleted file, its error fails the file
+    pub hook: Option<SyncHook>,
+    /// JSON file with state of last sync between local directory and AWS S3, files unchanged since then
+    /// are skipped without HeadObject requests or hashing. With state covering most files sync_up doesn't list
+    /// the prefix, so objects changed by others are not detected
+    pub state_file: Option<PathBuf>,
+    /// Conflict resolution of bidirectional sync
+    pub conflict_policy: ConflictPolicy,
+    /// Cancelling token stops transfers and deletions, unfinished files are reported as failed
+    /// with Cancelled error and the state is saved
+    pub cancel: Option<CancellationToken>,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: MAX_CONCURRENCY,
+            listing_concurrency: MAX_CONCURRENCY,
+            bytes_per_sec: None,
+            delete_extraneous: false,
+            dry_run: false,
+            filters: Filters::default(),
+            symlinks: SymlinkPolicy::default(),
+            compare: CompareStrategy::default(),
+            progress: None,
+            reporter: None,
+            hook: None,
+            state_file: None,
+            conflict_policy: ConflictPolicy::default(),
+            cancel: None,
+        }
+    }
+}
+
+/// Sync result with relative paths of transferred, skipped, deleted and failed files,
+/// with dry_run set nothing is transferred or deleted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub transferred: Vec<String>,
+    pub bytes_transferred: u64,
+    pub skipped: Vec<String>,
+    pub deleted: Vec<String>,
+    /// Failed transfers and deletions with error message
+    pub failed: Vec<(String, String)>,
+    pub duration: Duration,
+}
+
+impl SyncReport {
+    /// Effective throughput in bytes per second
+    pub fn throughput(&self) -> f64 {
+        match self.duration.as_secs_f64() {
+            secs if secs > 0.0 => self.bytes_transferred as f64 / secs,
+            _ => 0.0,
+        }
+    }
+
+    fn sort(&mut self) {
+        self.transferred.sort();
+        self.skipped.sort();
+        self.deleted.sort();
+        self.failed.sort();
+    }
+}
+
+/// Per-file sync progress event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncEvent {
+    Started(SyncAction),
+    Finished(SyncAction),
+    Failed(SyncAction, String),
+}
+
+/// Async callback invoked with each successfully applied sync action
+#[derive(Clone)]
+pub struct SyncHook(Arc<dyn Fn(SyncAction) -> HookFuture + Send + Sync>);
+
+type HookFuture = Pin<Box<dyn Future<Output = Result<(), UtilsError>> + Send>>;
+
+impl SyncHook {
+    pub fn new<F, Fut>(hook: F) -> Self
+    where
+        F: Fn(SyncAction) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), UtilsError>> + Send + 'static,
+    {
+        Self(Arc::new(move |action| Box::pin(hook(action))))
+    }
+
+    async fn call(&self, action: SyncAction) -> Result<(), UtilsError> {
+        (self.0)(action).await
+    }
+}
+
+impl fmt::Debug for SyncHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SyncHook")
+    }
+}
+
+/// Kind of sync action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncActionKind {
+    Upload,
+    Download,
+    Copy,
+    /// Delete destination file, AWS S3 object in bidirectional sync
+    Delete,
+    /// Delete local file in bidirectional sync
+    DeleteLocal,
+    Skip,
+}
+
+impl fmt::Display for SyncActionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            SyncActionKind::Upload => "UPLOAD",
+            SyncActionKind::Download => "DOWNLOAD",
+            SyncActionKind::Copy => "COPY",
+            SyncActionKind::Delete => "DELETE",
+            SyncActionKind::DeleteLocal => "DELETE_LOCAL",
+            SyncActionKind::Skip => "SKIP",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// Sync action for path relative to sync source and destination
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncAction {
+    pub kind: SyncActionKind,
+    pub relative: String,
+    /// Size of source file, or of destination file for DELETE
+    pub size: u64,
+    /// Modification time of source file, applied to downloaded files
+    pub modified: Option<SystemTime>,
+}
+
+impl fmt::Display for SyncAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} ({} bytes)", self.kind, self.relative, self.size)
+    }
+}
+
+/// Sync actions sorted by relative path
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncPlan {
+    pub actions: Vec<SyncAction>,
+}
+
+impl SyncPlan {
+    fn new(mut actions: Vec<SyncAction>) -> Self {
+        actions.sort_by(|a, b| a.relative.cmp(&b.relative));
+        Self { actions }
+    }
+
+    /// Get actions of kind
+    pub fn actions_of(&self, kind: SyncActionKind) -> impl Iterator<Item = &SyncAction> {
+        self.actions.iter().filter(move |action| action.kind == kind)
+    }
+
+    /// Total size of files to transfer
+    pub fn transfer_size(&self) -> u64 {
+        self.actions
+            .iter()
+            .filter(|action| !matches!(action.kind, SyncActionKind::Delete | SyncActionKind::DeleteLocal | SyncActionKind::Skip))
+            .map(|action| action.size)
+            .sum()
+    }
+
+    /// Get report of plan as if it was applied
+    pub fn report(&self) -> SyncReport {
+        let mut report = SyncReport::default();
+        for action in &self.actions {
+            match action.kind {
+                SyncActionKind::Upload | SyncActionKind::Download | SyncActionKind::Copy => {
+                    report.transferred.push(action.relative.clone());
+                    report.bytes_transferred += action.size;
+                }
+                SyncActionKind::Delete | SyncActionKind::DeleteLocal => report.deleted.push(action.relative.clone()),
+                SyncActionKind::Skip => report.skipped.push(action.relative.clone()),
+            }
+        }
+        report
+    }
+}
+
+impl fmt::Display for SyncPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for action in &self.actions {
+            writeln!(f, "{}", action)?;
+        }
+        Ok(())
+    }
+}
+
+/// Plan sync of local directory to AWS S3 prefix, files are uploaded if missing remotely
+/// or changed according to compare strategy. Objects missing locally
+/// are deleted if delete_extraneous is set
+pub async fn plan_sync_up(client: Client, local_dir: impl AsRef<Path>, s3path: &S3Path, opts: &SyncOptions) -> Result<SyncPlan, UtilsError> {
+    let local_dir = local_dir.as_ref();
+    let state = load_state(opts, local_dir, s3path).await?;
+    let (plan, _) = plan_up(client, local_dir, s3path, opts, state.as_ref()).await?;
+
+    Ok(plan)
+}
+
+/// Plan sync up and get state of files after plan is applied. Files missing in state or changed since
+/// are looked up with concurrent HeadObject requests, if they are most of the files the prefix is listed instead
+async fn plan_up(
+    client: Client,
+    local_dir: &Path,
+    s3path: &S3Path,
+    opts: &SyncOptions,
+    state: Option<&SyncState>,
+) -> Result<(SyncPlan, HashMap<String, FileState>), UtilsError> {
+    let mut local_files = list_local_files(local_dir, opts.symlinks).await?;
+    local_files.retain(|file| opts.filters.is_included(&file.relative));
+    let pending: Vec<&LocalFile> = local_files
+        .iter()
+        .filter(|file| recorded_state(state, file).is_none())
+        .collect();
+    let listed = state.is_none() || pending.len() * 2 > local_files.len();
+    let remote_files = if listed {
+        list_remote_files(client.clone(), s3path, opts).await?
+    } else {
+        let relatives: Vec<&str> = pending.iter().map(|file| file.relative.as_str()).collect();
+        get_remote_files(client.clone(), s3path, &relatives, opts).await?
+    };
+
+    plan_up_actions(client, &local_files, &remote_files, listed, &s3path.bucket, opts, state).await
+}
+
+/// Plan sync up of local files against remote files, with listed unset remote files have only files
+/// missing in state and extraneous objects are known only from state
+async fn plan_up_actions(
+    client: Client,
+    local_files: &[LocalFile],
+    remote_files: &HashMap<String, ObjectInfo>,
+    listed: bool,
+    bucket: &str,
+    opts: &SyncOptions,
+    state: Option<&SyncState>,
+) -> Result<(SyncPlan, HashMap<String, FileState>), UtilsError> {
+    let mut actions = Vec::new();
+    let mut files = HashMap::new();
+    for file in local_files {
+        if let Some(recorded) = recorded_state(state, file) {
+            actions.push(SyncAction { kind: SyncActionKind::Skip, relative: file.relative.clone(), size: file.size, modified: Some(file.modified) });
+            files.insert(file.relative.clone(), recorded.clone());
+            continue;
+        }
+
+        let remote = remote_files.get(&file.relative);
+        let changed = match remote {
+            Some(remote) => is_changed(client.clone(), bucket, file, remote, opts.compare, true).await?,
+            None => true,
+        };
+        let kind = if changed { SyncActionKind::Upload } else { SyncActionKind::Skip };
+        let e_tag = remote.and_then(|remote| remote.e_tag.clone()).filter(|_| !changed);
+        actions.push(SyncAction { kind, relative: file.relative.clone(), size: file.size, modified: Some(file.modified) });
+        files.insert(file.relative.clone(), FileState { size: file.size, e_tag, modified: secs(file.modified) });
+    }
+    if opts.delete_extraneous {
+        let local_relatives: HashSet<&str> = local_files.iter().map(|file| file.relative.as_str()).collect();
+        let remote_sizes: Vec<(&String, u64)> = match (listed, state) {
+            (true, _) => remote_files.iter().map(|(relative, remote)| (relative, remote.size as u64)).collect(),
+            (false, Some(state)) => state.files
+                .iter()
+                .filter(|(relative, _)| opts.filters.is_included(relative))
+                .map(|(relative, recorded)| (relative, recorded.size))
+                .collect(),
+            (false, None) => Vec::new(),
+        };
+        for (relative, size) in remote_sizes {
+            if !local_relatives.contains(relative.as_str()) {
+                actions.push(delete_action(relative, size));
+            }
+        }
+    }
+
+    Ok((SyncPlan::new(actions), files))
+}
+
+/// Recorded state of local file if it is unchanged since last sync
+fn recorded_state<'a>(state: Option<&'a SyncState>, file: &LocalFile) -> Option<&'a FileState> {
+    state
+        .and_then(|state| state.files.get(&file.relative))
+        .filter(|recorded| recorded.size == file.size && recorded.modified == secs(file.modified))
+}
+
+/// Sync local directory to AWS S3 prefix as planned by plan_sync_up,
+/// with dry_run set only the plan is made. Failed files are reported, not returned as error
+pub async fn sync_up(client: Client, local_dir: impl AsRef<Path>, s3path: &S3Path, opts: &SyncOptions) -> Result<SyncReport, UtilsError> {
+    let local_dir = local_dir.as_ref();
+    let start = Instant::now();
+    let state = load_state(opts, local_dir, s3path).await?;
+    let (plan, files) = plan_up(client.clone(), local_dir, s3path, opts, state.as_ref()).await?;
+    if opts.dry_run {
+        return Ok(plan.report());
+    }
+
+    let mut progress = Progress::new(&plan, opts, start);
+    let prefix = s3path.join("");
+    let manager = transfer_manager(client.clone(), opts);
+    let uploads = plan.actions_of(SyncActionKind::Upload).cloned().collect();
+    run_parallel(uploads, opts, &mut progress, |action| {
+        let manager = manager.clone();
+        let path = local_path(local_dir, &action.relative);
+        let dst = prefix.join(&action.relative);
+        async move {
+            manager.upload(path?, &dst).await?;
+            Ok(())
+        }
+    })
+    .await?;
+
+    let deletes: Vec<SyncAction> = plan.actions_of(SyncActionKind::Delete).cloned().collect();
+    let keys: Vec<String> = deletes.iter().map(|action| prefix.join(&action.relative).key).collect();
+    let res = cancellable(opts.cancel.as_ref(), delete_keys(client, &s3path.bucket, &keys)).await;
+    for action in deletes {
+        progress.start(&action);
+        progress.finish(action, res.as_ref().map(|_| ())).await;
+    }
+
+    let report = progress.into_report();
+    save_state(opts, state, files, &report).await?;
+
+    Ok(report)
+}
+
+/// Plan sync of AWS S3 prefix to local directory, objects are downloaded if missing locally
+/// or changed according to compare strategy. Local files missing in AWS S3
+/// are deleted if delete_extraneous is set
+pub async fn plan_sync_down(client: Client, s3path: &S3Path, local_dir: impl AsRef<Path>, opts: &SyncOptions) -> Result<SyncPlan, UtilsError> {
+    let local_dir = local_dir.as_ref();
+    let state = load_state(opts, local_dir, s3path).await?;
+    let (plan, _) = plan_down(client, s3path, local_dir, opts, state.as_ref()).await?;
+
+    Ok(plan)
+}
+
+/// Plan sync down and get state of files after plan is applied
+async fn plan_down(
+    client: Client,
+    s3path: &S3Path,
+    local_dir: &Path,
+    opts: &SyncOptions,
+    state: Option<&SyncState>,
+) -> Result<(SyncPlan, HashMap<String, FileState>), UtilsError> {
+    let local_files: HashMap<String, LocalFile> = if fs::try_exists(local_dir).await? {
+        list_local_files(local_dir, opts.symlinks)
+            .await?
+            .into_iter()
+            .filter(|file| opts.filters.is_included(&file.relative))
+            .map(|file| (file.relative.clone(), file))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    let remote_files = list_remote_files(client.clone(), s3path, opts).await?;
+
+    plan_down_actions(client, &local_files, &remote_files, &s3path.bucket, opts, state).await
+}
+
+/// Plan sync down of listed remote files against local files
+async fn plan_down_actions(
+    client: Client,
+    local_files: &HashMap<String, LocalFile>,
+    remote_files: &HashMap<String, ObjectInfo>,
+    bucket: &str,
+    opts: &SyncOptions,
+    state: Option<&SyncState>,
+) -> Result<(SyncPlan, HashMap<String, FileState>), UtilsError> {
+    let mut actions = Vec::new();
+    let mut files = HashMap::new();
+    for (relative, remote) in remote_files {
+        let local = local_files.get(relative);
+        let recorded = state
+            .and_then(|state| state.files.get(relative))
+            .filter(|recorded| recorded.size == remote.size as u64 && recorded.e_tag.is_some() && recorded.e_tag == remote.e_tag)
+            .filter(|recorded| local.is_some_and(|local| local.size == recorded.size && secs(local.modified) == recorded.modified));
+        let changed = match (recorded, local) {
+            (Some(_), _) => false,
+            (None, Some(local)) => is_changed(client.clone(), bucket, local, remote, opts.compare, false).await?,
+            (None, None) => true,
+        };
+        let kind = if changed { SyncActionKind::Download } else { SyncActionKind::Skip };
+        let modified = UNIX_EPOCH + Duration::from_secs(remote_secs(remote).max(0) as u64);
+        actions.push(SyncAction { kind, relative: relative.clone(), size: remote.size as u64, modified: Some(modified) });
+
+        let modified = match local {
+            Some(local) if !changed => secs(local.modified),
+            _ => remote_secs(remote),
+        };
+        files.insert(relative.clone(), FileState { size: remote.size as u64, e_tag: remote.e_tag.clone(), modified });
+    }
+    if opts.delete_extraneous {
+        for (relative, local) in local_files {
+            if !remote_files.contains_key(relative) {
+                actions.push(delete_action(relative, local.size));
+            }
+        }
+    }
+
+    Ok((SyncPlan::new(actions), files))
+}
+
+/// Sync AWS S3 prefix to local directory as planned by plan_sync_down,
+/// with dry_run set only the plan is made. Failed files are reported, not returned as error
+pub async fn sync_down(client: Client, s3path: &S3Path, local_dir: impl AsRef<Path>, opts: &SyncOptions) -> Result<SyncReport, UtilsError> {
+    let local_dir = local_dir.as_ref();
+    let start = Instant::now();
+    let state = load_state(opts, local_dir, s3path).await?;
+    let (plan, files) = plan_down(client.clone(), s3path, local_dir, opts, state.as_ref()).await?;
+    if opts.dry_run {
+        return Ok(plan.report());
+    }
+
+    let mut progress = Progress::new(&plan, opts, start);
+    let prefix = s3path.join("");
+    let manager = transfer_manager(client.clone(), opts);
+    let downloads = plan.actions_of(SyncActionKind::Download).cloned().collect();
+    run_parallel(downloads, opts, &mut progress, |action| {
+        let manager = manager.clone();
+        let path = local_path(local_dir, &action.relative);
+        let src = prefix.join(&action.relative);
+        async move { download_local_file(&manager, &src, &path?, action.modified).await }
+    })
+    .await?;
+
+    for action in plan.actions_of(SyncActionKind::Delete).cloned() {
+        progress.start(&action);
+        let res = cancellable(opts.cancel.as_ref(), async {
+            let path = local_path(local_dir, &action.relative)?;
+            Ok(fs::remove_file(path).await?)
+        })
+        .await;
+        progress.finish(action, res.as_ref().map(|_| ())).await;
+    }
+
+    let report = progress.into_report();
+    save_state(opts, state, files, &report).await?;
+
+    Ok(report)
+}
+
+/// Plan sync of AWS S3 prefix to another AWS S3 prefix, objects are copied if missing in destination
+/// or changed according to compare strategy, with Checksum strategy ETags are compared as is.
+/// Destination objects missing in source are deleted if delete_extraneous is set
+pub async fn plan_sync_s3(client: Client, src: &S3Path, dst: &S3Path, opts: &SyncOptions) -> Result<SyncPlan, UtilsError> {
+    let (src_files, dst_files) = tokio::try_join!(
+        list_remote_files(client.clone(), src, opts),
+        list_remote_files(client, dst, opts),
+    )?;
+
+    let mut actions = Vec::new();
+    for (relative, src_file) in &src_files {
+        let changed = match dst_files.get(relative) {
+            Some(dst_file) => src_file.size != dst_file.size || match opts.compare {
+                CompareStrategy::SizeMtime => remote_secs(src_file) > remote_secs(dst_file),
+                CompareStrategy::Checksum => src_file.e_tag.is_none() || src_file.e_tag != dst_file.e_tag,
+            },
+            None => true,
+        };
+        let kind = if changed { SyncActionKind::Copy } else { SyncActionKind::Skip };
+        actions.push(SyncAction { kind, relative: relative.clone(), size: src_file.size as u64, modified: None });
+    }
+    if opts.delete_extraneous {
+        for (relative, dst_file) in &dst_files {
+            if !src_files.contains_key(relative) {
+                actions.push(delete_action(relative, dst_file.size as u64));
+            }
+        }
+    }
+
+    Ok(SyncPlan::new(actions))
+}
+
+/// Sync AWS S3 prefix to another AWS S3 prefix server-side as planned by plan_sync_s3,
+/// with dry_run set only the plan is made. Failed files are reported, not returned as error
+pub async fn sync_s3(client: Client, src: &S3Path, dst: &S3Path, opts: &SyncOptions) -> Result<SyncReport, UtilsError> {
+    let start = Instant::now();
+    let plan = plan_sync_s3(client.clone(), src, dst, opts).await?;
+    if opts.dry_run {
+        return Ok(plan.report());
+    }
+
+    let mut progress = Progress::new(&plan, opts, start);
+    let (src_prefix, dst_prefix) = (src.join(""), dst.join(""));
+    let copies = plan.actions_of(SyncActionKind::Copy).cloned().collect();
+    run_parallel(copies, opts, &mut progress, |action| {
+        let client = client.clone();
+        let cancel = opts.cancel.clone();
+        let src = src_prefix.join(&action.relative);
+        let dst = dst_prefix.join(&action.relative);
+        async move { cancellable_copy_object(client, &src, &dst, &CopyOptions::default(), cancel.as_ref()).await }
+    })
+    .await?;
+
+    let deletes: Vec<SyncAction> = plan.actions_of(SyncActionKind::Delete).cloned().collect();
+    let keys: Vec<String> = deletes.iter().map(|action| dst_prefix.join(&action.relative).key).collect();
+    let res = cancellable(opts.cancel.as_ref(), delete_keys(client, &dst.bucket, &keys)).await;
+    for action in deletes {
+        progress.start(&action);
+        progress.finish(action, res.as_ref().map(|_| ())).await;
+    }
+
+    Ok(progress.into_report())
+}
+
+/// Plan bidirectional sync of local directory and AWS S3 prefix, files changed on one side
+/// since last sync recorded in state file are transferred or deleted on the other side,
+/// files changed on both sides are resolved with conflict policy
+pub async fn plan_sync_both(client: Client, local_dir: impl AsRef<Path>, s3path: &S3Path, opts: &SyncOptions) -> Result<SyncPlan, UtilsError> {
+    let local_dir = local_dir.as_ref();
+    let state = load_state(opts, local_dir, s3path).await?;
+    let (plan, _) = plan_both(client, local_dir, s3path, opts, state.as_ref()).await?;
+
+    Ok(plan)
+}
+
+/// Plan bidirectional sync and get state of files after plan is applied
+async fn plan_both(
+    client: Client,
+    local_dir: &Path,
+    s3path: &S3Path,
+    opts: &SyncOptions,
+    state: Option<&SyncState>,
+) -> Result<(SyncPlan, HashMap<String, FileState>), UtilsError> {
+    let Some(state) = state else {
+        return Err(UtilsError::UnexpectedError(eyre!("Bidirectional sync needs state_file")));
+    };
+    let local_files: HashMap<String, LocalFile> = if fs::try_exists(local_dir).await? {
+        list_local_files(local_dir, opts.symlinks)
+            .await?
+            .into_iter()
+            .filter(|file| opts.filters.is_included(&file.relative))
+            .map(|file| (file.relative.clone(), file))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    let remote_files = list_remote_files(client.clone(), s3path, opts).await?;
+
+    plan_both_actions(client, &local_files, &remote_files, &s3path.bucket, opts, state).await
+}
+
+/// Plan bidirectional sync of local and listed remote files against state of last sync
+async fn plan_both_actions(
+    client: Client,
+    local_files: &HashMap<String, LocalFile>,
+    remote_files: &HashMap<String, ObjectInfo>,
+    bucket: &str,
+    opts: &SyncOptions,
+    state: &SyncState,
+) -> Result<(SyncPlan, HashMap<String, FileState>), UtilsError> {
+    let mut relatives: Vec<&String> = local_files
+        .keys()
+        .chain(remote_files.keys())
+        .chain(state.files.keys().filter(|relative| opts.filters.is_included(relative)))
+        .collect();
+    relatives.sort();
+    relatives.dedup();
+
+    let mut actions = Vec::new();
+    let mut files = HashMap::new();
+    let mut conflicts = Vec::new();
+    for relative in relatives {
+        let (local, remote, recorded) = (local_files.get(relative), remote_files.get(relative), state.files.get(relative));
+        let local_changed = match (local, recorded) {
+            (Some(local), Some(recorded)) => local.size != recorded.size || secs(local.modified) != recorded.modified,
+            (None, None) => false,
+            _ => true,
+        };
+        let remote_changed = match (remote, recorded) {
+            (Some(remote), Some(recorded)) => remote.size as u64 != recorded.size || remote.e_tag.is_none() || remote.e_tag != recorded.e_tag,
+            (None, None) => false,
+            _ => true,
+        };
+
+        let kind = match (local_changed, remote_changed) {
+            (false, false) => SyncActionKind::Skip,
+            (true, false) if local.is_some() => SyncActionKind::Upload,
+            (true, false) => SyncActionKind::Delete,
+            (false, true) if remote.is_some() => SyncActionKind::Download,
+            (false, true) => SyncActionKind::DeleteLocal,
+            (true, true) => match (local, remote) {
+                (None, None) => continue,
+                (Some(local), Some(remote)) if !is_changed(client.clone(), bucket, local, remote, CompareStrategy::Checksum, true).await? => {
+                    SyncActionKind::Skip
+                }
+                _ => match resolve_conflict(local, remote, opts.conflict_policy) {
+                    Some(kind) => kind,
+                    None => {
+                        conflicts.push(relative.clone());
+                        continue;
+                    }
+                },
+            },
+        };
+
+        let action = match (kind, local, remote) {
+            (SyncActionKind::Upload, Some(local), _) | (SyncActionKind::DeleteLocal, Some(local), _) => {
+                SyncAction { kind, relative: relative.clone(), size: local.size, modified: Some(local.modified) }
+            }
+            (_, _, Some(remote)) => {
+                let modified = UNIX_EPOCH + Duration::from_secs(remote_secs(remote).max(0) as u64);
+                SyncAction { kind, relative: relative.clone(), size: remote.size as u64, modified: Some(modified) }
+            }
+            (_, Some(local), None) => SyncAction { kind, relative: relative.clone(), size: local.size, modified: Some(local.modified) },
+            (_, None, None) => continue,
+        };
+        let file_state = match (kind, local, remote) {
+            (SyncActionKind::Upload, Some(local), _) => Some(FileState { size: local.size, e_tag: None, modified: secs(local.modified) }),
+            (SyncActionKind::Download, _, Some(remote)) => {
+                Some(FileState { size: remote.size as u64, e_tag: remote.e_tag.clone(), modified: remote_secs(remote) })
+            }
+            (SyncActionKind::Skip, Some(local), Some(remote)) => {
+                Some(FileState { size: local.size, e_tag: remote.e_tag.clone(), modified: secs(local.modified) })
+            }
+            _ => None,
+        };
+        if let Some(file_state) = file_state {
+            files.insert(relative.clone(), file_state);
+        }
+        actions.push(action);
+    }
+    if !conflicts.is_empty() {
+        let err = eyre!("Sync conflicts: {}", conflicts.join(", "));
+        return Err(UtilsError::UnexpectedError(err));
+    }
+
+    Ok((SyncPlan::new(actions), files))
+}
+
+/// Sync local directory and AWS S3 prefix both ways as planned by plan_sync_both,
+/// with dry_run set only the plan is made. Failed files are reported, not returned as error
+pub async fn sync_both(client: Client, local_dir: impl AsRef<Path>, s3path: &S3Path, opts: &SyncOptions) -> Result<SyncReport, UtilsError> {
+    let local_dir = local_dir.as_ref();
+    let start = Instant::now();
+    let state = load_state(opts, local_dir, s3path).await?;
+    let (plan, mut files) = plan_both(client.clone(), local_dir, s3path, opts, state.as_ref()).await?;
+    if opts.dry_run {
+        return Ok(plan.report());
+    }
+
+    let mut progress = Progress::new(&plan, opts, start);
+    let prefix = s3path.join("");
+    let transfers: Vec<SyncAction> = plan
+        .actions
+        .iter()
+        .filter(|action| matches!(action.kind, SyncActionKind::Upload | SyncActionKind::Download))
+        .cloned()
+        .collect();
+    let uploaded = transfers.iter().any(|action| action.kind == SyncActionKind::Upload);
+    let manager = transfer_manager(client.clone(), opts);
+    run_parallel(transfers, opts, &mut progress, |action| {
+        let manager = manager.clone();
+        let path = local_path(local_dir, &action.relative);
+        let remote = prefix.join(&action.relative);
+        async move {
+            let path = path?;
+            match action.kind {
+                SyncActionKind::Upload => manager.upload(&path, &remote).await.map(|_| ()),
+                _ => download_local_file(&manager, &remote, &path, action.modified).await,
+            }
+        }
+    })
+    .await?;
+
+    let deletes: Vec<SyncAction> = plan.actions_of(SyncActionKind::Delete).cloned().collect();
+    let keys: Vec<String> = deletes.iter().map(|action| prefix.join(&action.relative).key).collect();
+    let res = cancellable(opts.cancel.as_ref(), delete_keys(client.clone(), &s3path.bucket, &keys)).await;
+    for action in deletes {
+        progress.start(&action);
+        progress.finish(action, res.as_ref().map(|_| ())).await;
+    }
+    for action in plan.actions_of(SyncActionKind::DeleteLocal).cloned() {
+        progress.start(&action);
+        let res = cancellable(opts.cancel.as_ref(), async {
+            let path = local_path(local_dir, &action.relative)?;
+            Ok(fs::remove_file(path).await?)
+        })
+        .await;
+        progress.finish(action, res.as_ref().map(|_| ())).await;
+    }
+
+    // ETags of uploaded objects are needed to detect remote changes on next sync
+    if uploaded {
+        let remote_files = list_remote_files(client, s3path, opts).await?;
+        for (relative, file_state) in files.iter_mut() {
+            if file_state.e_tag.is_none() {
+                file_state.e_tag = remote_files.get(relative).and_then(|remote| remote.e_tag.clone());
+            }
+        }
+    }
+
+    let report = progress.into_report();
+    save_state(opts, state, files, &report).await?;
+
+    Ok(report)
+}
+
+/// Resolve file changed on both sides, None if policy is Fail
+fn resolve_conflict(local: Option<&LocalFile>, remote: Option<&ObjectInfo>, policy: ConflictPolicy) -> Option<SyncActionKind> {
+    let kind = match (policy, local, remote) {
+        (ConflictPolicy::Fail, _, _) => return None,
+        (ConflictPolicy::PreferLocal, Some(_), _) => SyncActionKind::Upload,
+        (ConflictPolicy::PreferLocal, None, _) => SyncActionKind::Delete,
+        (ConflictPolicy::PreferRemote, _, Some(_)) => SyncActionKind::Download,
+        (ConflictPolicy::PreferRemote, _, None) => SyncActionKind::DeleteLocal,
+        (ConflictPolicy::NewestWins, Some(local), Some(remote)) if secs(local.modified) > remote_secs(remote) => SyncActionKind::Upload,
+        (ConflictPolicy::NewestWins, _, Some(_)) => SyncActionKind::Download,
+        (ConflictPolicy::NewestWins, _, None) => SyncActionKind::Upload,
+    };
+
+    Some(kind)
+}
+
+/// Load sync state if state file is set
+async fn load_state(opts: &SyncOptions, local_dir: &Path, s3path: &S3Path) -> Result<Option<SyncState>, UtilsError> {
+    match &opts.state_file {
+        Some(path) => Ok(Some(SyncState::load(path, local_dir, s3path).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Save state of synced files, failed files keep their previous state
+async fn save_state(
+    opts: &SyncOptions,
+    state: Option<SyncState>,
+    mut files: HashMap<String, FileState>,
+    report: &SyncReport,
+) -> Result<(), UtilsError> {
+    let (Some(path), Some(mut state)) = (&opts.state_file, state) else {
+        return Ok(());
+    };
+
+    for (relative, _) in &report.failed {
+        match state.files.remove(relative) {
+            Some(recorded) => files.insert(relative.clone(), recorded),
+            None => files.remove(relative),
+        };
+    }
+    state.files = files;
+
+    state.save(path).await
+}
+
+/// Get object info with HeadObject request, None if key doesn't exist
+/// Get objects of relative paths under prefix with concurrent HeadObject requests, missing objects are omitted
+async fn get_remote_files(client: Client, s3path: &S3Path, relatives: &[&str], opts: &SyncOptions) -> Result<HashMap<String, ObjectInfo>, UtilsError> {
+    let prefix = s3path.join("");
+    let keys: Vec<String> = relatives.iter().map(|relative| prefix.join(relative).key).collect();
+    let mut metadata = head_objects(client, &prefix.bucket, &keys, opts.listing_concurrency).await?;
+
+    let mut files = HashMap::new();
+    for (relative, key) in relatives.iter().zip(keys) {
+        let Some(metadata) = metadata.remove(&key).transpose()?.flatten() else {
+            continue;
+        };
+        files.insert(relative.to_string(), ObjectInfo {
+            key,
+            size: metadata.size,
+            e_tag: metadata.e_tag,
+            last_modified: metadata.last_modified,
+            storage_class: metadata.storage_class,
+            content_type: metadata.content_type,
+        });
+    }
+
+    Ok(files)
+}
+
+fn delete_action(relative: &str, size: u64) -> SyncAction {
+    SyncAction { kind: SyncActionKind::Delete, relative: relative.to_string(), size, modified: None }
+}
+
+/// Report of applied plan, progress events are sent as actions start and finish
+struct Progress<'a> {
+    sender: Option<&'a UnboundedSender<SyncEvent>>,
+    reporter: Option<&'a dyn ProgressReporter>,
+    hook: Option<&'a SyncHook>,
+    report: SyncReport,
+    start: Instant,
+}
+
+impl<'a> Progress<'a> {
+    fn new(plan: &SyncPlan, opts: &'a SyncOptions, start: Instant) -> Self {
+        let report = SyncReport {
+            skipped: plan.actions_of(SyncActionKind::Skip).map(|action| action.relative.clone()).collect(),
+            ..Default::default()
+        };
+        Self {
+            sender: opts.progress.as_ref(),
+            reporter: opts.reporter.as_deref(),
+            hook: opts.hook.as_ref(),
+            report,
+            start,
+        }
+    }
+
+    fn emit(&self, event: SyncEvent) {
+        if let Some(sender) = self.sender {
+            // receiver may be dropped if caller is not interested in remaining events
+            let _ = sender.send(event);
+        }
+    }
+
+    fn start(&self, action: &SyncAction) {
+        notify_start(self.sender, self.reporter, action);
+    }
+
+    /// Record finished action, successful actions are passed to hook first
+    async fn finish(&mut self, action: SyncAction, res: Result<(), &UtilsError>) {
+        let hook_res = match (res, self.hook) {
+            (Ok(()), Some(hook)) => hook.call(action.clone()).await,
+            _ => Ok(()),
+        };
+        let res = res.and(hook_res.as_ref().map(|_| ()));
+        if let Some(reporter) = self.reporter {
+            if res.is_ok() {
+                reporter.on_bytes(&action.relative, action.size);
+            }
+            reporter.on_finish(&action.relative, res.is_ok());
+        }
+        match res {
+            Ok(()) if matches!(action.kind, SyncActionKind::Delete | SyncActionKind::DeleteLocal) => {
+                debug!(action = %action.kind, path = %action.relative, "sync delete finished");
+                self.report.deleted.push(action.relative.clone());
+                self.emit(SyncEvent::Finished(action));
+            }
+            Ok(()) => {
+                debug!(action = %action.kind, path = %action.relative, bytes = action.size, "sync transfer finished");
+                self.report.transferred.push(action.relative.clone());
+                self.report.bytes_transferred += action.size;
+                self.emit(SyncEvent::Finished(action));
+            }
+            Err(err) => {
+                let message = error_message(err);
+                warn!(action = %action.kind, path = %action.relative, error = %message, "sync action failed");
+                self.report.failed.push((action.relative.clone(), message.clone()));
+                self.emit(SyncEvent::Failed(action, message));
+            }
+        }
+    }
+
+    fn into_report(mut self) -> SyncReport {
+        self.report.duration = self.start.elapsed();
+        self.report.sort();
+        self.report
+    }
+}
+
+/// Error message with all sources and AWS request id
+fn error_message(err: &UtilsError) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        message.push_str(": ");
+        message.push_str(&err.to_string());
+        source = err.source();
+    }
+    if let Some(request_id) = err.request_id() {
+        message.push_str(&format!(" (request id: {})", request_id));
+    }
+    message
+}
+
+/// Run transfer for every action with at most concurrency transfers in parallel,
+/// after cancel remaining actions fail. Transfers handle cancel themselves,
+/// so uploads can abort their multipart uploads
+async fn run_parallel<F, Fut>(actions: Vec<SyncAction>, opts: &SyncOptions, progress: &mut Progress<'_>, transfer: F) -> Result<(), UtilsError>
+where
+    F: Fn(SyncAction) -> Fut,
+    Fut: Future<Output = Result<(), UtilsError>> + Send + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(opts.concurrency.max(1)));
+    let mut tasks: JoinSet<(SyncAction, Result<(), UtilsError>)> = JoinSet::new();
+    for action in actions {
+        let fut = transfer(action.clone());
+        let semaphore = semaphore.clone();
+        let sender = opts.progress.clone();
+        let reporter = opts.reporter.clone();
+        let cancel = opts.cancel.clone();
+        tasks.spawn(async move {
+            let _permit = match semaphore.acquire().await {
+                Ok(permit) => permit,
+                Err(err) => return (action, Err(err.into())),
+            };
+            notify_start(sender.as_ref(), reporter.as_deref(), &action);
+            if cancel.is_some_and(|cancel| cancel.is_cancelled()) {
+                return (action, Err(UtilsError::Cancelled));
+            }
+            let res = fut.await;
+            (action, res)
+        });
+    }
+
+    while let Some(res) = tasks.join_next().await {
+        let (action, res) = res?;
+        progress.finish(action, res.as_ref().map(|_| ())).await;
+    }
+
+    Ok(())
+}
+
+/// Report started action, called from transfer tasks once they hold a permit
+fn notify_start(sender: Option<&UnboundedSender<SyncEvent>>, reporter: Option<&dyn ProgressReporter>, action: &SyncAction) {
+    if let Some(reporter) = reporter {
+        reporter.on_start(&action.relative, action.size);
+    }
+    if let Some(sender) = sender {
+        // receiver may be dropped if caller is not interested in remaining events
+        let _ = sender.send(SyncEvent::Started(action.clone()));
+    }
+}
+
+/// Transfer manager of sync uploads and downloads stopped by cancel token of options,
+/// clones share one bandwidth limit throttling every part while it is transferred
+fn transfer_manager(client: Client, opts: &SyncOptions) -> TransferManager {
+    let mut manager = TransferManager::new(client);
+    if let Some(bytes_per_sec) = opts.bytes_per_sec {
+        manager = manager.bytes_per_sec(bytes_per_sec);
+    }
+    match &opts.cancel {
+        Some(cancel) => manager.cancellation_token(cancel.clone()),
+        None => manager,
+    }
+}
+
+/// Download object creating parent directories and set file modification time if given
+async fn download_local_file(manager: &TransferManager, src: &S3Path, path: &Path, modified: Option<SystemTime>) -> Result<(), UtilsError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    manager.download(src, path).await?;
+
+    if let Some(modified) = modified {
+        let file = fs::File::options().write(true).open(path).await?.into_std().await;
+        file.set_modified(modified)?;
+    }
+
+    Ok(())
+}
+
+/// Get objects under prefix passing filters by path relative to prefix
+async fn list_remote_files(client: Client, s3path: &S3Path, opts: &SyncOptions) -> Result<HashMap<String, ObjectInfo>, UtilsError> {
+    let prefix = s3path.join("");
+    let files = list_objects_info_parallel(client, &prefix.bucket, &prefix.key, opts.listing_concurrency)
+        .await?
+        .into_iter()
+        .filter_map(|file| {
+            let relative = file.key.strip_prefix(&prefix.key)?.to_string();
+            opts.filters.is_included(&relative).then_some((relative, file))
+        })
+        .collect();
+
+    Ok(files)
+}
+
+/// Compare local file with object using strategy, with SizeMtime the source must be newer
+async fn is_changed(
+    client: Client,
+    bucket: &str,
+    local: &LocalFile,
+    remote: &ObjectInfo,
+    strategy: CompareStrategy,
+    local_is_source: bool,
+) -> Result<bool, UtilsError> {
+    if local.size != remote.size as u64 {
+        return Ok(true);
+    }
+
+    match strategy {
+        CompareStrategy::SizeMtime if local_is_source => Ok(secs(local.modified) > remote_secs(remote)),
+        CompareStrategy::SizeMtime => Ok(remote_secs(remote) > secs(local.modified)),
+        CompareStrategy::Checksum => {
+            let e_tag = remote.e_tag.as_deref().unwrap_or_default();
+            let matches = etag_matches(client, bucket, &remote.key, e_tag, &local.path).await?;
+            Ok(!matches)
+        }
+    }
+}
+
+fn secs(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn remote_secs(file: &ObjectInfo) -> i64 {
+    file.last_modified.map(|val| val.secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_smithy_types::DateTime;
+
+    use super::*;
+
+    fn local_client() -> Client {
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
+            .region(aws_sdk_s3::config::Region::new("us-east-1"))
+            .endpoint_url("http://localhost:9000")
+            .build();
+        Client::from_conf(config)
+    }
+
+    fn local(relative: &str, size: u64, modified: u64) -> LocalFile {
+        LocalFile {
+            path: PathBuf::from("dir").join(relative),
+            relative: relative.to_string(),
+            size,
+            modified: UNIX_EPOCH + Duration::from_secs(modified),
+        }
+    }
+
+    fn remote(relative: &str, size: i64, e_tag: &str, modified: i64) -> ObjectInfo {
+        ObjectInfo {
+            key: format!("prefix/{}", relative),
+            size,
+            e_tag: Some(e_tag.to_string()),
+            last_modified: Some(DateTime::from_secs(modified)),
+            storage_class: None,
+            content_type: None,
+        }
+    }
+
+    fn state(files: &[(&str, u64, &str, i64)]) -> SyncState {
+        let mut state = SyncState::new(Path::new("dir"), &S3Path::new("bucket", "prefix"));
+        for (relative, size, e_tag, modified) in files {
+            state.files.insert(relative.to_string(), FileState { size: *size, e_tag: Some(e_tag.to_string()), modified: *modified });
+        }
+        state
+    }
+
+    fn by_relative<T>(files: Vec<T>, relative: impl Fn(&T) -> &str) -> HashMap<String, T> {
+        files.into_iter().map(|file| (relative(&file).to_string(), file)).collect()
+    }
+
+    fn kinds(plan: &SyncPlan) -> Vec<(&str, SyncActionKind)> {
+        plan.actions.iter().map(|action| (action.relative.as_str(), action.kind)).collect()
+    }
+
+    #[tokio::test]
+    async fn plan_up_uploads_missing_and_newer_files() {
+        let local_files = vec![local("a", 1, 100), local("b", 1, 200), local("c", 1, 100), local("d", 2, 100)];
+        let remote_files = by_relative(vec![remote("b", 1, "eb", 100), remote("c", 1, "ec", 200), remote("d", 1, "ed", 200)], |file| {
+            file.key.strip_prefix("prefix/").unwrap()
+        });
+        let opts = SyncOptions::default();
+        let (plan, files) = plan_up_actions(local_client(), &local_files, &remote_files, true, "bucket", &opts, None).await.unwrap();
+
+        assert_eq!(kinds(&plan), vec![
+            ("a", SyncActionKind::Upload),
+            ("b", SyncActionKind::Upload),
+            ("c", SyncActionKind::Skip),
+            ("d", SyncActionKind::Upload),
+        ]);
+        assert_eq!(files["c"], FileState { size: 1, e_tag: Some("ec".to_string()), modified: 100 });
+        assert_eq!(files["a"].e_tag, None);
+    }
+
+    #[tokio::test]
+    async fn plan_up_skips_files_unchanged_since_state() {
+        let local_files = vec![local("a", 1, 100), local("b", 1, 300)];
+        let state = state(&[("a", 1, "ea", 100), ("b", 1, "eb", 100)]);
+        let opts = SyncOptions::default();
+        let (plan, files) = plan_up_actions(local_client(), &local_files, &HashMap::new(), false, "bucket", &opts, Some(&state)).await.unwrap();
+
+        assert_eq!(kinds(&plan), vec![("a", SyncActionKind::Skip), ("b", SyncActionKind::Upload)]);
+        assert_eq!(files["a"], state.files["a"]);
+        assert_eq!(files["b"], FileState { size: 1, e_tag: None, modified: 300 });
+    }
+
+    #[tokio::test]
+    async fn plan_up_deletes_only_recorded_keys_without_listing() {
+        let local_files = vec![local("a", 1, 100)];
+        let state = state(&[("a", 1, "ea", 100), ("gone", 5, "eg", 100), ("skip.tmp", 5, "es", 100)]);
+        let remote_files: HashMap<String, ObjectInfo> = [("other".to_string(), remote("other", 7, "eo", 100))].into();
+        let opts = SyncOptions {
+            delete_extraneous: true,
+            filters: Filters::new().exclude("*.tmp"),
+            ..SyncOptions::default()
+        };
+        let (plan, _) = plan_up_actions(local_client(), &local_files, &remote_files, false, "bucket", &opts, Some(&state)).await.unwrap();
+        assert_eq!(kinds(&plan), vec![("a", SyncActionKind::Skip), ("gone", SyncActionKind::Delete)]);
+        assert_eq!(plan.actions[1].size, 5);
+
+        let (plan, _) = plan_up_actions(local_client(), &local_files, &remote_files, true, "bucket", &opts, Some(&state)).await.unwrap();
+        assert_eq!(kinds(&plan), vec![("a", SyncActionKind::Skip), ("other", SyncActionKind::Delete)]);
+    }
+
+    #[tokio::test]
+    async fn plan_down_downloads_missing_and_newer_objects() {
+        let local_files = by_relative(vec![local("b", 1, 200), local("c", 1, 100), local("d", 1, 100), local("extra", 3, 100)], |file| &file.relative);
+        let remote_files = by_relative(vec![remote("a", 1, "ea", 100), remote("b", 1, "eb", 100), remote("c", 1, "ec", 200), remote("d", 1, "ed", 200)], |file| {
+            file.key.strip_prefix("prefix/").unwrap()
+        });
+        let state = state(&[("d", 1, "ed", 100)]);
+        let opts = SyncOptions { delete_extraneous: true, ..SyncOptions::default() };
+        let (plan, files) = plan_down_actions(local_client(), &local_files, &remote_files, "bucket", &opts, Some(&state)).await.unwrap();
+
+        assert_eq!(kinds(&plan), vec![
+            ("a", SyncActionKind::Download),
+            ("b", SyncActionKind::Skip),
+            ("c", SyncActionKind::Download),
+            ("d", SyncActionKind::Skip),
+            ("extra", SyncActionKind::Delete),
+        ]);
+        assert_eq!(files["b"], FileState { size: 1, e_tag: Some("eb".to_string()), modified: 200 });
+        assert_eq!(files["c"].modified, 200);
+        assert!(!files.contains_key("extra"));
+    }
+
+    #[tokio::test]
+    async fn plan_both_propagates_changes_and_resolves_conflicts() {
+        let local_files = by_relative(vec![local("a", 2, 300), local("b", 1, 100), local("e", 2, 300), local("new", 1, 100)], |file| &file.relative);
+        let remote_files = by_relative(vec![remote("a", 1, "ea", 100), remote("b", 1, "eb2", 200), remote("c", 1, "ec", 100)], |file| {
+            file.key.strip_prefix("prefix/").unwrap()
+        });
+        let state = state(&[("a", 1, "ea", 100), ("b", 1, "eb", 100), ("c", 1, "ec", 100), ("d", 1, "ed", 100), ("e", 1, "ee", 100)]);
+        let opts = SyncOptions::default();
+        let (plan, _) = plan_both_actions(local_client(), &local_files, &remote_files, "bucket", &opts, &state).await.unwrap();
+
+        assert_eq!(kinds(&plan), vec![
+            ("a", SyncActionKind::Upload),
+            ("b", SyncActionKind::Download),
+            ("c", SyncActionKind::Delete),
+            ("e", SyncActionKind::Upload),
+            ("new", SyncActionKind::Upload),
+        ]);
+
+        let opts = SyncOptions { conflict_policy: ConflictPolicy::PreferRemote, ..SyncOptions::default() };
+        let (plan, _) = plan_both_actions(local_client(), &local_files, &remote_files, "bucket", &opts, &state).await.unwrap();
+        assert_eq!(plan.actions.iter().find(|action| action.relative == "e").unwrap().kind, SyncActionKind::DeleteLocal);
+
+        let opts = SyncOptions { conflict_policy: ConflictPolicy::Fail, ..SyncOptions::default() };
+        assert!(plan_both_actions(local_client(), &local_files, &remote_files, "bucket", &opts, &state).await.is_err());
+    }
+}
@@ -0,0 +1,78 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::utils::S3Path;
+use crate::error::UtilsError;
+
+/// File state recorded after successful sync, modified is local file modification time in seconds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileState {
+    pub size: u64,
+    pub e_tag: Option<String>,
+    pub modified: i64,
+}
+
+/// State of local directory synced with AWS S3 prefix by relative path, stored as JSON
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncState {
+    pub local_dir: String,
+    pub s3_uri: String,
+    pub files: HashMap<String, FileState>,
+}
+
+impl SyncState {
+    pub fn new(local_dir: &Path, s3path: &S3Path) -> Self {
+        Self {
+            local_dir: local_dir.to_string_lossy().into_owned(),
+            s3_uri: s3path.uri(),
+            files: HashMap::new(),
+        }
+    }
+
+    /// Load state from file, empty if file doesn't exist or was recorded for other directory or prefix
+    pub async fn load(path: &Path, local_dir: &Path, s3path: &S3Path) -> Result<Self, UtilsError> {
+        let empty = Self::new(local_dir, s3path);
+        if !fs::try_exists(path).await? {
+            return Ok(empty);
+        }
+
+        let state: Self = serde_json::from_slice(&fs::read(path).await?)?;
+        if state.local_dir != empty.local_dir || state.s3_uri != empty.s3_uri {
+            return Ok(empty);
+        }
+
+        Ok(state)
+    }
+
+    /// Save state to file, replacing it atomically
+    pub async fn save(&self, path: &Path) -> Result<(), UtilsError> {
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(self)?).await?;
+        fs::rename(&tmp_path, path).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn state_round_trip_and_mismatch() {
+        let path = std::env::temp_dir().join(format!("sync-state-{}.json", std::process::id()));
+        let s3path = S3Path::new("bucket", "prefix");
+        assert_eq!(SyncState::load(&path, Path::new("dir"), &s3path).await.unwrap(), SyncState::new(Path::new("dir"), &s3path));
+
+        let mut state = SyncState::new(Path::new("dir"), &s3path);
+        state.files.insert("a".to_string(), FileState { size: 1, e_tag: Some("\"ea\"".to_string()), modified: 100 });
+        state.save(&path).await.unwrap();
+        assert_eq!(SyncState::load(&path, Path::new("dir"), &s3path).await.unwrap(), state);
+        assert!(SyncState::load(&path, Path::new("other"), &s3path).await.unwrap().files.is_empty());
+        assert!(SyncState::load(&path, Path::new("dir"), &S3Path::new("bucket", "other")).await.unwrap().files.is_empty());
+
+        fs::remove_file(&path).await.unwrap();
+    }
+}
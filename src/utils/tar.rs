@@ -0,0 +1,23 @@
+use std::path::Path;
+
+use aws_sdk_s3::Client;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio_tar::Archive;
+use tracing::info;
+
+use crate::utils::{get_aws_object_with_options, Codec, GetOptions};
+use crate::error::UtilsError;
+
+/// Extract tar archive to local directory while it is downloaded, gzip and zstd
+/// compression is detected by magic bytes, entries outside dest_dir are skipped
+pub async fn extract_tar(client: Client, bucket: &str, key: &str, dest_dir: impl AsRef<Path>) -> Result<(), UtilsError> {
+    let res = get_aws_object_with_options(client, bucket, key, &GetOptions::default()).await?;
+    let mut body = BufReader::new(res.body.into_async_read());
+    let codec = Codec::from_magic(body.fill_buf().await?);
+
+    tokio::fs::create_dir_all(dest_dir.as_ref()).await?;
+    Archive::new(codec.decoder(body)).unpack(dest_dir.as_ref()).await?;
+    info!(bucket, key, dest_dir = %dest_dir.as_ref().display(), codec = ?codec, "extracted tar archive");
+
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::{sync::Mutex, time::{sleep_until, Instant}};
+
+/// Bandwidth limiter shared by parallel transfers, each transfer reserves time slot for its size,
+/// so the limit holds on average and not within single transfer
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    bytes_per_sec: u64,
+    next: Arc<Mutex<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec.max(1),
+            next: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Wait until bytes can be transferred without exceeding the limit
+    pub async fn acquire(&self, bytes: u64) {
+        let start = {
+            let mut next = self.next.lock().await;
+            let start = (*next).max(Instant::now());
+            *next = start + Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec as f64);
+            start
+        };
+        sleep_until(start).await;
+    }
+}
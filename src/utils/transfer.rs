@@ -0,0 +1,612 @@
+use std::{future::Future, io::SeekFrom, path::{Path, PathBuf}, sync::Arc, time::{Duration, Instant}};
+#[cfg(feature = "compression")]
+use std::{io, pin::Pin, task::{Context, Poll}};
+
+use aws_config::retry::RetryConfig;
+use aws_sdk_s3::{primitives::ByteStream, types::{CompletedMultipartUpload, CompletedPart}, Client};
+use aws_smithy_types::byte_stream::Length;
+use tokio::{fs::{self, File}, io::{AsyncSeekExt, AsyncWriteExt, BufWriter}, sync::Semaphore, task::JoinSet};
+#[cfg(feature = "compression")]
+use tokio::io::{AsyncRead, AsyncReadExt, BufReader, ReadBuf};
+use tracing::{debug, info, warn};
+
+#[cfg(feature = "compression")]
+use crate::utils::{Codec, S3Writer, READ_BUF_SIZE};
+use crate::utils::{cancellable, compute_sha256, head_object, write_sidecar, with_retry, CancellationToken, ProgressReporter, RateLimiter, RetryCounter, RetryPolicy, S3Path, TransferReport, CHUNK_SIZE, MAX_CHUNKS, MAX_CONCURRENCY};
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+/// Uploads and downloads files choosing single request or parallel multipart upload / ranged download
+/// by size, configured once with chunk size, concurrency, retries and bandwidth limit
+#[derive(Debug, Clone)]
+pub struct TransferManager {
+    client: Client,
+    multipart_threshold: u64,
+    chunk_size: u64,
+    concurrency: usize,
+    limiter: Option<RateLimiter>,
+    reporter: Option<Arc<dyn ProgressReporter>>,
+    cancel: Option<CancellationToken>,
+    deadline: Option<Duration>,
+    retry: RetryPolicy,
+    sidecar_sha256: bool,
+    #[cfg(feature = "compression")]
+    codec: Codec,
+}
+
+impl TransferManager {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            multipart_threshold: CHUNK_SIZE,
+            chunk_size: CHUNK_SIZE,
+            concurrency: MAX_CONCURRENCY,
+            limiter: None,
+            reporter: None,
+            cancel: None,
+            deadline: None,
+            retry: RetryPolicy::default(),
+            sidecar_sha256: false,
+            #[cfg(feature = "compression")]
+            codec: Codec::None,
+        }
+    }
+
+    /// Files larger than threshold are transferred in parts
+    pub fn multipart_threshold(mut self, threshold: u64) -> Self {
+        self.multipart_threshold = threshold;
+        self
+    }
+
+    /// Part size, increased if file would need more than MAX_CHUNKS parts
+    pub fn chunk_size(mut self, chunk_size: u64) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Max number of parts transferred in parallel
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Max attempts of every request
+    pub fn retries(mut self, max_attempts: u32) -> Self {
+        let config = self.client
+            .config()
+            .to_builder()
+            .retry_config(RetryConfig::standard().with_max_attempts(max_attempts))
+            .build();
+        self.client = Client::from_conf(config);
+        self
+    }
+
+    /// Retry policy of single part uploads and ranged downloads, on top of retries of requests,
+    /// also retries failures while streaming response body
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Max average bandwidth in bytes per second shared by all parts
+    pub fn bytes_per_sec(mut self, bytes_per_sec: u64) -> Self {
+        self.limiter = Some(RateLimiter::new(bytes_per_sec));
+        self
+    }
+
+    /// Receiver of progress of every transfer
+    pub fn reporter(mut self, reporter: Arc<dyn ProgressReporter>) -> Self {
+        self.reporter = Some(reporter);
+        self
+    }
+
+    /// Cancelling token stops transfers with Cancelled error, multipart uploads are aborted
+    /// and partially downloaded files removed
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Max duration of whole transfer including all parts and retries, expired transfer fails
+    /// with Timeout error like cancelled one
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Also write <key>.sha256 sidecar object with SHA-256 of every uploaded file, of uncompressed file with codec
+    pub fn sidecar_sha256(mut self, sidecar_sha256: bool) -> Self {
+        self.sidecar_sha256 = sidecar_sha256;
+        self
+    }
+
+    /// Compress uploads with codec setting Content-Encoding and decompress downloads with it,
+    /// compressed transfers are streamed sequentially and progress counts uncompressed bytes
+    #[cfg(feature = "compression")]
+    pub fn codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Upload local file to AWS S3
+    pub async fn upload(&self, path: impl AsRef<Path>, s3path: &S3Path) -> Result<TransferReport, UtilsError> {
+        let path = path.as_ref();
+        let start = Instant::now();
+        let counter = RetryCounter::default();
+        let mut res = self.upload_file(path, s3path, &counter, start).await;
+        if res.is_ok() && self.sidecar_sha256 {
+            res = self.upload_sidecar(path, s3path).await.and(res);
+        }
+        self.notify(|reporter| reporter.on_finish(&s3path.key, res.is_ok()));
+        res
+    }
+
+    /// Download object from AWS S3 to local file, data is written to <path>.part
+    /// renamed to path on success and removed on failure
+    pub async fn download(&self, s3path: &S3Path, path: impl AsRef<Path>) -> Result<TransferReport, UtilsError> {
+        let path = path.as_ref();
+        let partial_path = partial_path(path);
+        let start = Instant::now();
+        let counter = RetryCounter::default();
+        let mut res = self.guard(self.download_file(s3path, &partial_path, &counter, start), &counter, start).await;
+        match &res {
+            Ok(_) => {
+                if let Err(err) = fs::rename(&partial_path, path).await {
+                    res = Err(err.into());
+                }
+            }
+            Err(err) => {
+                warn!(bucket = %s3path.bucket, key = %s3path.key, path = %path.display(), error = %err, "download failed, removing partial file");
+                let _ = fs::remove_file(&partial_path).await;
+            }
+        }
+        self.notify(|reporter| reporter.on_finish(&s3path.key, res.is_ok()));
+        res
+    }
+
+    async fn upload_file(&self, path: &Path, s3path: &S3Path, counter: &RetryCounter, start: Instant) -> Result<TransferReport, UtilsError> {
+        let file_size = fs::metadata(path).await?.len();
+        self.notify(|reporter| reporter.on_start(&s3path.key, file_size));
+        #[cfg(feature = "compression")]
+        if self.codec != Codec::None {
+            return self.guard(self.upload_compressed(path, s3path, counter), counter, start).await;
+        }
+        if file_size <= self.multipart_threshold {
+            self.throttle(file_size).await;
+            let body = ByteStream::from_path(path).await?;
+            let req = self.client
+                .put_object()
+                .bucket(&s3path.bucket)
+                .key(&s3path.key)
+                .body(body)
+                .customize()
+                .interceptor(counter.clone());
+            let res = self.guard(async {
+                req.send().await.with_context(|| ErrorContext::object("PutObject", &s3path.bucket, &s3path.key))
+            }, counter, start).await?;
+            counter.add_bytes(file_size);
+            self.notify(|reporter| reporter.on_bytes(&s3path.key, file_size));
+            return Ok(report(file_size, 1, counter, start, res.e_tag()));
+        }
+
+        let req = self.client
+            .create_multipart_upload()
+            .bucket(&s3path.bucket)
+            .key(&s3path.key)
+            .customize()
+            .interceptor(counter.clone());
+        let res = self.guard(async {
+            req.send().await.with_context(|| ErrorContext::object("CreateMultipartUpload", &s3path.bucket, &s3path.key))
+        }, counter, start).await?;
+        let upload_id = res
+            .upload_id()
+            .ok_or_else(|| UtilsError::MissingUploadId(s3path.uri()))?;
+        info!(bucket = %s3path.bucket, key = %s3path.key, bytes = file_size, upload_id, "multipart upload started");
+
+        let parts = self.upload_parts(path, s3path, upload_id, file_size, counter);
+        match self.guard(parts, counter, start).await {
+            Ok(parts) => {
+                let part_count = parts.len() as u32;
+                let res = self.client
+                    .complete_multipart_upload()
+                    .bucket(&s3path.bucket)
+                    .key(&s3path.key)
+                    .upload_id(upload_id)
+                    .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+                    .customize()
+                    .interceptor(counter.clone())
+                    .send()
+                    .await
+                    .with_context(|| ErrorContext::object("CompleteMultipartUpload", &s3path.bucket, &s3path.key))?;
+                info!(bucket = %s3path.bucket, key = %s3path.key, bytes = file_size, upload_id, "multipart upload completed");
+                Ok(report(file_size, part_count, counter, start, res.e_tag()))
+            }
+            Err(err) => {
+                warn!(bucket = %s3path.bucket, key = %s3path.key, upload_id, error = %err, request_id = err.request_id(), "multipart upload failed, aborting");
+                // parts of failed upload are billed until upload is aborted, abort failure is only logged
+                // as original error is returned
+                let res = self.client
+                    .abort_multipart_upload()
+                    .bucket(&s3path.bucket)
+                    .key(&s3path.key)
+                    .upload_id(upload_id)
+                    .send()
+                    .await;
+                if let Err(abort_err) = res {
+                    warn!(bucket = %s3path.bucket, key = %s3path.key, upload_id, error = %abort_err, "failed to abort multipart upload");
+                }
+                Err(err)
+            }
+        }
+    }
+
+    async fn download_file(&self, s3path: &S3Path, path: &Path, counter: &RetryCounter, start: Instant) -> Result<TransferReport, UtilsError> {
+        #[cfg(feature = "compression")]
+        if self.codec != Codec::None {
+            return self.download_compressed(s3path, path, counter, start).await;
+        }
+        let head = head_object(self.client.clone(), &s3path.bucket, &s3path.key)
+            .await?
+            .ok_or_else(|| UtilsError::ObjectNotFound(s3path.uri()))?;
+        let file_size = head.content_length().unwrap_or(0) as u64;
+        // requests are pinned to the ETag of HeadObject so that an object overwritten
+        // during the download fails it instead of mixing old and new data
+        let e_tag = head.e_tag().map(|val| val.to_string());
+        self.notify(|reporter| reporter.on_start(&s3path.key, file_size));
+        if file_size <= self.multipart_threshold {
+            self.throttle(file_size).await;
+            let object = self.client
+                .get_object()
+                .bucket(&s3path.bucket)
+                .key(&s3path.key)
+                .set_if_match(e_tag.clone())
+                .customize()
+                .interceptor(counter.clone())
+                .send()
+                .await
+                .with_context(|| ErrorContext::object("GetObject", &s3path.bucket, &s3path.key))?;
+            let mut writer = BufWriter::new(File::create(path).await?);
+            let mut body = object.body;
+            let mut received = 0;
+            while let Some(bytes) = body.try_next().await? {
+                writer.write_all(&bytes).await?;
+                received += bytes.len() as u64;
+            }
+            writer.flush().await?;
+            if received != file_size {
+                return Err(UtilsError::SizeMismatch { expected: file_size, actual: received });
+            }
+            counter.add_bytes(file_size);
+            self.notify(|reporter| reporter.on_bytes(&s3path.key, file_size));
+            return Ok(report(file_size, 1, counter, start, e_tag.as_deref()));
+        }
+
+        File::create(path).await?.set_len(file_size).await?;
+        let part_size = self.part_size(file_size);
+        info!(bucket = %s3path.bucket, key = %s3path.key, bytes = file_size, part_size, "ranged download started");
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let mut tasks = JoinSet::new();
+        for (index, offset) in (0..file_size).step_by(part_size as usize).enumerate() {
+            let semaphore = semaphore.clone();
+            let manager = self.clone();
+            let s3path = s3path.clone();
+            let path = path.to_path_buf();
+            let counter = counter.clone();
+            let e_tag = e_tag.clone();
+            let len = part_size.min(file_size - offset);
+            let part_number = index as u32 + 1;
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire().await?;
+                with_retry(&manager.retry, || {
+                    manager.download_range(&s3path, &path, e_tag.as_deref(), part_number, (offset, len), &counter)
+                })
+                .await
+            });
+        }
+
+        let mut part_count = 0;
+        while let Some(res) = tasks.join_next().await {
+            res??;
+            part_count += 1;
+        }
+
+        Ok(report(file_size, part_count, counter, start, e_tag.as_deref()))
+    }
+
+    async fn upload_sidecar(&self, path: &Path, s3path: &S3Path) -> Result<(), UtilsError> {
+        let sha256 = compute_sha256(path).await?;
+        write_sidecar(self.client.clone(), &s3path.bucket, &s3path.key, &sha256).await
+    }
+
+    #[cfg(feature = "compression")]
+    async fn upload_compressed(&self, path: &Path, s3path: &S3Path, counter: &RetryCounter) -> Result<TransferReport, UtilsError> {
+        let file = Counting {
+            inner: File::open(path).await?,
+            on_read: |bytes| self.notify(|reporter| reporter.on_bytes(&s3path.key, bytes)),
+        };
+        let mut reader = self.codec.encoder(BufReader::new(file));
+        let mut writer = S3Writer::new(self.client.clone(), &s3path.bucket, &s3path.key)
+            .part_size(self.chunk_size as usize)
+            .content_encoding(self.codec.content_encoding().unwrap_or_default());
+
+        let mut buf = vec![0; READ_BUF_SIZE];
+        loop {
+            let len = reader.read(&mut buf).await?;
+            if len == 0 {
+                break;
+            }
+            self.throttle(len as u64).await;
+            writer.write(&buf[..len]).await?;
+            counter.add_bytes(len as u64);
+        }
+        let report = writer.finish().await?;
+        info!(bucket = %s3path.bucket, key = %s3path.key, bytes = report.bytes, codec = ?self.codec, "compressed upload completed");
+
+        Ok(report)
+    }
+
+    #[cfg(feature = "compression")]
+    async fn download_compressed(&self, s3path: &S3Path, path: &Path, counter: &RetryCounter, start: Instant) -> Result<TransferReport, UtilsError> {
+        let object = self.client
+            .get_object()
+            .bucket(&s3path.bucket)
+            .key(&s3path.key)
+            .customize()
+            .interceptor(counter.clone())
+            .send()
+            .await
+            .with_context(|| ErrorContext::object("GetObject", &s3path.bucket, &s3path.key))?;
+        let e_tag = object.e_tag().map(|val| val.to_string());
+        self.notify(|reporter| reporter.on_start(&s3path.key, 0));
+
+        let mut reader = Counting {
+            inner: self.codec.decoder(object.body.into_async_read()),
+            on_read: |bytes| self.notify(|reporter| reporter.on_bytes(&s3path.key, bytes)),
+        };
+        let mut writer = BufWriter::new(File::create(path).await?);
+        let mut buf = vec![0; READ_BUF_SIZE];
+        loop {
+            let len = reader.read(&mut buf).await?;
+            if len == 0 {
+                break;
+            }
+            self.throttle(len as u64).await;
+            writer.write_all(&buf[..len]).await?;
+            counter.add_bytes(len as u64);
+        }
+        writer.flush().await?;
+
+        Ok(report(counter.bytes(), 1, counter, start, e_tag.as_deref()))
+    }
+
+    async fn upload_parts(
+        &self,
+        path: &Path,
+        s3path: &S3Path,
+        upload_id: &str,
+        file_size: u64,
+        counter: &RetryCounter,
+    ) -> Result<Vec<CompletedPart>, UtilsError> {
+        let part_size = self.part_size(file_size);
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        // all parts are spawned at once, permits taken inside tasks bound parts in flight
+        let mut tasks = JoinSet::new();
+        for (index, offset) in (0..file_size).step_by(part_size as usize).enumerate() {
+            let semaphore = semaphore.clone();
+            let manager = self.clone();
+            let s3path = s3path.clone();
+            let path = path.to_path_buf();
+            let upload_id = upload_id.to_string();
+            let counter = counter.clone();
+            let len = part_size.min(file_size - offset);
+            let part_number = index as i32 + 1;
+            tasks.spawn(async move {
+                let res = async {
+                    let _permit = semaphore.acquire().await?;
+                    manager.throttle(len).await;
+                    with_retry(&manager.retry, || {
+                        manager.upload_part(&path, &s3path, &upload_id, part_number, (offset, len), &counter)
+                    })
+                    .await
+                }
+                .await;
+                (part_number, res)
+            });
+        }
+
+        // first failure cancels outstanding parts, remaining tasks are drained
+        // so that no part is uploaded after the upload is aborted
+        let mut parts = Vec::new();
+        let mut failed_parts = Vec::new();
+        let mut first_err = None;
+        while let Some(res) = tasks.join_next().await {
+            let err = match res {
+                Ok((_, Ok(part))) => {
+                    parts.push(part);
+                    continue;
+                }
+                Ok((part_number, Err(err))) => {
+                    warn!(bucket = %s3path.bucket, key = %s3path.key, part = part_number, error = %err, "part upload failed");
+                    failed_parts.push(part_number);
+                    err
+                }
+                Err(err) if err.is_cancelled() => continue,
+                Err(err) => err.into(),
+            };
+            if first_err.is_none() {
+                tasks.abort_all();
+                first_err = Some(err);
+            }
+        }
+        if let Some(err) = first_err {
+            failed_parts.sort();
+            return Err(UtilsError::PartsFailed { parts: failed_parts, source: Box::new(err) });
+        }
+        parts.sort_by_key(|part| part.part_number());
+
+        Ok(parts)
+    }
+
+    async fn upload_part(
+        &self,
+        path: &Path,
+        s3path: &S3Path,
+        upload_id: &str,
+        part_number: i32,
+        (offset, len): (u64, u64),
+        counter: &RetryCounter,
+    ) -> Result<CompletedPart, UtilsError> {
+        let body = ByteStream::read_from()
+            .path(path)
+            .offset(offset)
+            .length(Length::Exact(len))
+            .build()
+            .await?;
+        let res = self.client
+            .upload_part()
+            .bucket(&s3path.bucket)
+            .key(&s3path.key)
+            .upload_id(upload_id)
+            .part_number(part_number)
+            .body(body)
+            .customize()
+            .interceptor(counter.clone())
+            .send()
+            .await
+            .with_context(|| ErrorContext::object("UploadPart", &s3path.bucket, &s3path.key).part_number(part_number))?;
+
+        debug!(bucket = %s3path.bucket, key = %s3path.key, part = part_number, bytes = len, "uploaded part");
+        counter.add_bytes(len);
+        self.notify(|reporter| {
+            reporter.on_bytes(&s3path.key, len);
+            reporter.on_part_done(&s3path.key, part_number as u32);
+        });
+        let part = CompletedPart::builder()
+            .set_e_tag(res.e_tag().map(|val| val.to_string()))
+            .part_number(part_number)
+            .build();
+
+        Ok(part)
+    }
+
+    async fn download_range(
+        &self,
+        s3path: &S3Path,
+        path: &Path,
+        e_tag: Option<&str>,
+        part_number: u32,
+        (offset, len): (u64, u64),
+        counter: &RetryCounter,
+    ) -> Result<(), UtilsError> {
+        self.throttle(len).await;
+        let object = self.client
+            .get_object()
+            .bucket(&s3path.bucket)
+            .key(&s3path.key)
+            .range(format!("bytes={}-{}", offset, offset + len - 1))
+            .set_if_match(e_tag.map(|val| val.to_string()))
+            .customize()
+            .interceptor(counter.clone())
+            .send()
+            .await
+            .with_context(|| ErrorContext::object("GetObject", &s3path.bucket, &s3path.key))?;
+
+        let mut file = fs::OpenOptions::new().write(true).open(path).await?;
+        file.seek(SeekFrom::Start(offset)).await?;
+        let mut writer = BufWriter::new(file);
+        let mut body = object.body;
+        let mut received = 0;
+        while let Some(bytes) = body.try_next().await? {
+            writer.write_all(&bytes).await?;
+            received += bytes.len() as u64;
+        }
+        writer.flush().await?;
+        if received != len {
+            return Err(UtilsError::SizeMismatch { expected: len, actual: received });
+        }
+        debug!(bucket = %s3path.bucket, key = %s3path.key, offset, bytes = len, "downloaded range");
+        counter.add_bytes(len);
+        self.notify(|reporter| {
+            reporter.on_bytes(&s3path.key, len);
+            reporter.on_part_done(&s3path.key, part_number);
+        });
+
+        Ok(())
+    }
+
+    /// Get part size keeping number of parts within MAX_CHUNKS
+    fn part_size(&self, file_size: u64) -> u64 {
+        self.chunk_size.max(file_size.div_ceil(MAX_CHUNKS))
+    }
+
+    /// Run future until cancelled or deadline of transfer started at start expires
+    async fn guard<T>(&self, fut: impl Future<Output = Result<T, UtilsError>>, counter: &RetryCounter, start: Instant) -> Result<T, UtilsError> {
+        let fut = cancellable(self.cancel.as_ref(), fut);
+        let Some(deadline) = self.deadline else {
+            return fut.await;
+        };
+        match tokio::time::timeout_at((start + deadline).into(), fut).await {
+            Ok(res) => res,
+            Err(_) => Err(UtilsError::Timeout { elapsed: start.elapsed(), bytes: counter.bytes() }),
+        }
+    }
+
+    fn notify(&self, f: impl FnOnce(&dyn ProgressReporter)) {
+        if let Some(reporter) = &self.reporter {
+            f(reporter.as_ref());
+        }
+    }
+
+    async fn throttle(&self, bytes: u64) {
+        if let Some(limiter) = &self.limiter {
+            limiter.acquire(bytes).await;
+        }
+    }
+}
+
+/// Reader calling on_read with number of bytes of every read
+#[cfg(feature = "compression")]
+struct Counting<R, F> {
+    inner: R,
+    on_read: F,
+}
+
+#[cfg(feature = "compression")]
+impl<R: AsyncRead + Unpin, F: FnMut(u64) + Unpin> AsyncRead for Counting<R, F> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let res = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let bytes = buf.filled().len() - filled;
+        if bytes > 0 {
+            (self.on_read)(bytes as u64);
+        }
+        res
+    }
+}
+
+/// Get path of partial download: <path>.part in the same directory so that rename is atomic
+fn partial_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".part");
+    path.with_file_name(file_name)
+}
+
+fn report(bytes: u64, parts: u32, counter: &RetryCounter, start: Instant, e_tag: Option<&str>) -> TransferReport {
+    TransferReport {
+        bytes,
+        parts,
+        retries: counter.retries(),
+        duration: start.elapsed(),
+        e_tag: e_tag.map(|val| val.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_path_is_next_to_path() {
+        assert_eq!(partial_path(Path::new("data/report.csv")), PathBuf::from("data/report.csv.part"));
+        assert_eq!(partial_path(Path::new("report")), PathBuf::from("report.part"));
+    }
+}
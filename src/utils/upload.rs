@@ -0,0 +1,226 @@
+use std::{path::Path, time::Instant};
+
+use color_eyre::eyre::eyre;
+use aws_sdk_s3::{primitives::ByteStream, types::{CompletedMultipartUpload, CompletedPart, StorageClass}, Client};
+use aws_smithy_types::byte_stream::Length;
+use tokio::fs::File;
+use tracing::{debug, info, warn};
+
+use crate::utils::{compute_sha256, head_object, write_sidecar, PutOptions, RetryCounter, S3Key, TransferReport, CHUNK_SIZE, MAX_CHUNKS, MIN_PART_SIZE};
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+pub async fn upload_file(client: Client, bucket: &str, file_path: impl AsRef<Path>, key: impl Into<S3Key>) -> Result<(), UtilsError> {	
+	let s3_key = S3Key::new(key)?;
+	let key = s3_key.as_str();
+	let body = ByteStream::from_path(file_path.as_ref()).await?;
+
+	let resp = client
+		.put_object()
+		.bucket(bucket)
+		.key(key)
+		.body(body);
+
+	resp.send().await.with_context(|| ErrorContext::object("PutObject", bucket, key))?;
+
+	Ok(())
+}
+
+/// Upload file by chunks with checking size
+#[deprecated(since = "0.1.0", note = "use upload_object_multipart_with_options, sizes are set with PutOptions")]
+pub async fn upload_object_multipart(
+    client: Client, 
+    bucket: &str, 
+    file_name: &str, 
+    key: &str, 
+    file_size: Option<u64>, 
+    chunk_size: Option<u64>, 
+    max_chunks: Option<u64>,
+) -> Result<(), UtilsError> {
+    let opts = PutOptions {
+        file_size,
+        chunk_size,
+        max_chunks,
+        ..PutOptions::default()
+    };
+    upload_object_multipart_with_options(client, bucket, file_name, key, &opts).await.map(|_| ())
+}
+
+/// Upload file by chunks with checking size, content type, storage class and metadata of options,
+/// chunk size must be at least MIN_PART_SIZE, failed upload is aborted
+pub async fn upload_object_multipart_with_options(
+    client: Client, 
+    bucket: &str, 
+    file_name: impl AsRef<Path>, 
+    key: impl Into<S3Key>, 
+    opts: &PutOptions,
+) -> Result<TransferReport, UtilsError> {
+    let s3_key = S3Key::new(key)?;
+    let key = s3_key.as_str();
+    let start = Instant::now();
+    let counter = RetryCounter::default();
+    let path = file_name.as_ref();
+    let file_size = match opts.file_size {
+        Some(val) => val,
+        None => {
+            File::open(path).await?.metadata().await?.len()
+        }
+    };
+    let chunk_size = opts.chunk_size.unwrap_or(CHUNK_SIZE);
+    let max_chunks = opts.max_chunks.unwrap_or(MAX_CHUNKS);
+    if file_size == 0 {
+        let err = eyre!(format!("Bad file size for: {}", path.display()));
+        return Err(UtilsError::UnexpectedError(err));
+    }
+    if chunk_size < MIN_PART_SIZE as u64 {
+        return Err(UtilsError::PartTooSmall { size: chunk_size, min: MIN_PART_SIZE as u64 });
+    }
+    let mut chunk_count = (file_size / chunk_size) + 1;
+    let mut size_of_last_chunk = file_size % chunk_size;
+
+    if size_of_last_chunk == 0 {
+        size_of_last_chunk = chunk_size;
+        chunk_count -= 1;
+    }
+    if chunk_count > max_chunks {
+        return Err(UtilsError::TooManyChunks { count: chunk_count, max: max_chunks });
+    }
+
+    let multipart_upload_res = client
+        .create_multipart_upload()
+        .bucket(bucket)
+        .key(key)
+        .set_content_type(opts.content_type.clone())
+        .set_storage_class(opts.storage_class.as_deref().map(StorageClass::from))
+        .set_metadata(Some(opts.metadata.clone()).filter(|metadata| !metadata.is_empty()))
+        .customize()
+        .interceptor(counter.clone())
+        .send()
+        .await
+        .with_context(|| ErrorContext::object("CreateMultipartUpload", bucket, key))?;
+
+    let upload_id = multipart_upload_res
+        .upload_id()
+        .ok_or_else(|| UtilsError::MissingUploadId(format!("s3://{}/{}", bucket, key)))?;
+    info!(bucket, key, file_name = %path.display(), upload_id, "uploading file");
+
+    let res = async {
+        let mut upload_parts = Vec::new();
+        for chunk_index in 0..chunk_count {
+            let this_chunk = if chunk_count - 1 == chunk_index {
+                size_of_last_chunk
+            } else {
+                chunk_size
+            };
+            let stream = ByteStream::read_from()
+                .path(path)
+                .offset(chunk_index * chunk_size)
+                .length(Length::Exact(this_chunk))
+                .build()
+                .await?;
+
+            let part_number = (chunk_index as i32) + 1;
+            let upload_part_res = client
+                .upload_part()
+                .key(key)
+                .bucket(bucket)
+                .upload_id(upload_id)
+                .body(stream)
+                .part_number(part_number)
+                .customize()
+                .interceptor(counter.clone())
+                .send()
+                .await
+                .with_context(|| ErrorContext::object("UploadPart", bucket, key).part_number(part_number))?;
+
+            debug!(bucket, key, part = part_number, bytes = this_chunk, "uploaded part");
+            upload_parts.push(
+                CompletedPart::builder()
+                    .e_tag(upload_part_res.e_tag.ok_or_else(|| UtilsError::MissingETag(format!("s3://{}/{} part {}", bucket, key, part_number)))?)
+                    .part_number(part_number)
+                    .build(),
+            );
+        }
+
+        let completed_multipart_upload = CompletedMultipartUpload::builder()
+            .set_parts(Some(upload_parts))
+            .build();
+
+        client
+            .complete_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .multipart_upload(completed_multipart_upload)
+            .upload_id(upload_id)
+            .customize()
+            .interceptor(counter.clone())
+            .send()
+            .await
+            .with_context(|| ErrorContext::object("CompleteMultipartUpload", bucket, key))
+    }
+    .await;
+    let complete_multipart_upload_res = match res {
+        Ok(res) => res,
+        Err(err) => {
+            warn!(bucket, key, upload_id, error = %err, "multipart upload failed, aborting");
+            // parts of failed upload are billed until upload is aborted, abort failure is only logged
+            // as original error is returned
+            let abort_res = client
+                .abort_multipart_upload()
+                .bucket(bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .send()
+                .await;
+            if let Err(abort_err) = abort_res {
+                warn!(bucket, key, upload_id, error = %abort_err, "failed to abort multipart upload");
+            }
+            return Err(err);
+        }
+    };
+
+    info!(bucket, key, file_name = %path.display(), bytes = file_size, parts = chunk_count, "uploaded file");
+
+    let data = head_object(client.clone(), bucket, key).await?;
+    let data_length = data.and_then(|data| data.content_length()).unwrap_or(0) as u64;
+    if file_size != data_length {
+        return Err(UtilsError::SizeMismatch { expected: file_size, actual: data_length });
+    }
+
+    if opts.sidecar_sha256 {
+        let sha256 = compute_sha256(path).await?;
+        write_sidecar(client, bucket, key, &sha256).await?;
+    }
+
+    let report = TransferReport {
+        bytes: file_size,
+        parts: chunk_count as u32,
+        retries: counter.retries(),
+        duration: start.elapsed(),
+        e_tag: complete_multipart_upload_res.e_tag().map(|val| val.to_string()),
+    };
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local_client() -> Client {
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
+            .region(aws_sdk_s3::config::Region::new("us-east-1"))
+            .endpoint_url("http://localhost:9000")
+            .build();
+        Client::from_conf(config)
+    }
+
+    #[tokio::test]
+    async fn multipart_upload_rejects_small_chunks() {
+        for chunk_size in [0, 1024, MIN_PART_SIZE as u64 - 1] {
+            let opts = PutOptions::default().file_size(100).chunk_size(chunk_size);
+            let err = upload_object_multipart_with_options(local_client(), "bucket", "missing", "key", &opts).await.unwrap_err();
+            assert!(matches!(err, UtilsError::PartTooSmall { size, .. } if size == chunk_size), "{:?}", err);
+        }
+    }
+}
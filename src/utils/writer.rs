@@ -0,0 +1,217 @@
+use std::time::Instant;
+
+use aws_sdk_s3::{primitives::ByteStream, types::{CompletedMultipartUpload, CompletedPart, StorageClass}, Client};
+use tracing::{debug, info, warn};
+
+use crate::utils::{RetryCounter, TransferReport, CHUNK_SIZE, MIN_PART_SIZE};
+use crate::error::{ErrorContext, ResultExt, UtilsError};
+
+/// Writer of AWS S3 object of unknown size: data is buffered and uploaded by parts of multipart upload,
+/// small objects are uploaded by single PutObject on finish, failed upload is aborted
+#[derive(Debug)]
+pub struct S3Writer {
+    client: Client,
+    bucket: String,
+    key: String,
+    part_size: usize,
+    content_type: Option<String>,
+    content_encoding: Option<String>,
+    storage_class: Option<String>,
+    buf: Vec<u8>,
+    upload_id: Option<String>,
+    parts: Vec<CompletedPart>,
+    bytes: u64,
+    counter: RetryCounter,
+    start: Instant,
+}
+
+impl S3Writer {
+    pub fn new(client: Client, bucket: impl Into<String>, key: impl Into<String>) -> Self {
+        Self {
+            client,
+            bucket: bucket.into(),
+            key: key.into(),
+            part_size: CHUNK_SIZE as usize,
+            content_type: None,
+            content_encoding: None,
+            storage_class: None,
+            buf: Vec::new(),
+            upload_id: None,
+            parts: Vec::new(),
+            bytes: 0,
+            counter: RetryCounter::default(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Size of uploaded parts, at least 5 MiB
+    pub fn part_size(mut self, part_size: usize) -> Self {
+        self.part_size = part_size.max(MIN_PART_SIZE);
+        self
+    }
+
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    pub fn content_encoding(mut self, content_encoding: impl Into<String>) -> Self {
+        self.content_encoding = Some(content_encoding.into());
+        self
+    }
+
+    /// Storage class like STANDARD_IA or GLACIER
+    pub fn storage_class(mut self, storage_class: impl Into<String>) -> Self {
+        self.storage_class = Some(storage_class.into());
+        self
+    }
+
+    /// Get number of bytes written so far
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Buffer data, uploading full parts
+    pub async fn write(&mut self, data: &[u8]) -> Result<(), UtilsError> {
+        self.buf.extend_from_slice(data);
+        self.bytes += data.len() as u64;
+        while self.buf.len() >= self.part_size {
+            let rest = self.buf.split_off(self.part_size);
+            let part = std::mem::replace(&mut self.buf, rest);
+            if let Err(err) = self.upload_part(part).await {
+                self.abort().await;
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Upload buffered data and complete object
+    pub async fn finish(mut self) -> Result<TransferReport, UtilsError> {
+        let res = match self.upload_id.is_some() {
+            true => self.complete().await,
+            false => self.put().await,
+        };
+        if res.is_err() {
+            self.abort().await;
+        }
+        let e_tag = res?;
+
+        info!(bucket = %self.bucket, key = %self.key, bytes = self.bytes, parts = self.parts.len(), "written object");
+        Ok(TransferReport {
+            bytes: self.bytes,
+            parts: self.parts.len().max(1) as u32,
+            retries: self.counter.retries(),
+            duration: self.start.elapsed(),
+            e_tag,
+        })
+    }
+
+    async fn put(&mut self) -> Result<Option<String>, UtilsError> {
+        let res = self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .set_content_type(self.content_type.clone())
+            .set_content_encoding(self.content_encoding.clone())
+            .set_storage_class(self.storage_class.as_deref().map(StorageClass::from))
+            .body(ByteStream::from(std::mem::take(&mut self.buf)))
+            .customize()
+            .interceptor(self.counter.clone())
+            .send()
+            .await
+            .with_context(|| ErrorContext::object("PutObject", &self.bucket, &self.key))?;
+
+        Ok(res.e_tag().map(|val| val.to_string()))
+    }
+
+    async fn complete(&mut self) -> Result<Option<String>, UtilsError> {
+        if !self.buf.is_empty() {
+            let part = std::mem::take(&mut self.buf);
+            self.upload_part(part).await?;
+        }
+
+        let res = self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .upload_id(self.upload_id.as_deref().unwrap_or_default())
+            .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(self.parts.clone())).build())
+            .customize()
+            .interceptor(self.counter.clone())
+            .send()
+            .await
+            .with_context(|| ErrorContext::object("CompleteMultipartUpload", &self.bucket, &self.key))?;
+
+        Ok(res.e_tag().map(|val| val.to_string()))
+    }
+
+    async fn upload_part(&mut self, data: Vec<u8>) -> Result<(), UtilsError> {
+        let upload_id = match &self.upload_id {
+            Some(upload_id) => upload_id.clone(),
+            None => {
+                let res = self.client
+                    .create_multipart_upload()
+                    .bucket(&self.bucket)
+                    .key(&self.key)
+                    .set_content_type(self.content_type.clone())
+                    .set_content_encoding(self.content_encoding.clone())
+                    .set_storage_class(self.storage_class.as_deref().map(StorageClass::from))
+                    .customize()
+                    .interceptor(self.counter.clone())
+                    .send()
+                    .await
+                    .with_context(|| ErrorContext::object("CreateMultipartUpload", &self.bucket, &self.key))?;
+                let upload_id = res
+                    .upload_id()
+                    .ok_or_else(|| UtilsError::MissingUploadId(format!("s3://{}/{}", self.bucket, self.key)))?
+                    .to_string();
+                self.upload_id = Some(upload_id.clone());
+                upload_id
+            }
+        };
+
+        let part_number = self.parts.len() as i32 + 1;
+        let bytes = data.len();
+        let res = self.client
+            .upload_part()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .upload_id(upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(data))
+            .customize()
+            .interceptor(self.counter.clone())
+            .send()
+            .await
+            .with_context(|| ErrorContext::object("UploadPart", &self.bucket, &self.key).part_number(part_number))?;
+
+        debug!(bucket = %self.bucket, key = %self.key, part = part_number, bytes, "uploaded part");
+        self.parts.push(
+            CompletedPart::builder()
+                .e_tag(res.e_tag.ok_or_else(|| UtilsError::MissingETag(format!("s3://{}/{} part {}", self.bucket, self.key, part_number)))?)
+                .part_number(part_number)
+                .build(),
+        );
+
+        Ok(())
+    }
+
+    /// Abort started multipart upload, failure is only logged as original error is returned
+    async fn abort(&mut self) {
+        let Some(upload_id) = self.upload_id.take() else {
+            return;
+        };
+        let res = self.client
+            .abort_multipart_upload()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .upload_id(&upload_id)
+            .send()
+            .await;
+        if let Err(err) = res {
+            warn!(bucket = %self.bucket, key = %self.key, upload_id, error = %err, "failed to abort multipart upload");
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::path::Path;
+
+use async_zip::tokio::read::seek::ZipFileReader;
+use aws_sdk_s3::Client;
+use color_eyre::eyre::eyre;
+use tokio::{fs::File, io::{AsyncWriteExt, BufReader, BufWriter}};
+use tokio_util::compat::FuturesAsyncReadCompatExt;
+use tracing::info;
+
+use crate::utils::{S3Path, S3SeekableReader};
+use crate::error::UtilsError;
+
+type S3ZipReader = ZipFileReader<BufReader<S3SeekableReader>>;
+
+/// Entry of zip archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipMember {
+    pub name: String,
+    pub size: u64,
+    pub compressed_size: u64,
+    pub crc32: u32,
+    pub is_dir: bool,
+}
+
+/// List entries of zip archive, only central directory is downloaded
+pub async fn zip_list(client: Client, bucket: &str, key: &str) -> Result<Vec<ZipMember>, UtilsError> {
+    let zip = open_zip(client, bucket, key).await?;
+
+    let mut members = Vec::new();
+    for entry in zip.file().entries() {
+        members.push(ZipMember {
+            name: entry.filename().as_str()?.to_string(),
+            size: entry.uncompressed_size(),
+            compressed_size: entry.compressed_size(),
+            crc32: entry.crc32(),
+            is_dir: entry.dir()?,
+        });
+    }
+
+    Ok(members)
+}
+
+/// Extract single entry of zip archive to local file, only central directory
+/// and entry data are downloaded, returns number of extracted bytes
+pub async fn zip_extract_file(client: Client, bucket: &str, key: &str, member: &str, dest: impl AsRef<Path>) -> Result<u64, UtilsError> {
+    let mut zip = open_zip(client, bucket, key).await?;
+    let index = zip
+        .file()
+        .entries()
+        .iter()
+        .position(|entry| entry.filename().as_str().is_ok_and(|name| name == member))
+        .ok_or_else(|| UtilsError::UnexpectedError(eyre!("No {} in zip archive s3://{}/{}", member, bucket, key)))?;
+
+    let crc32 = zip.file().entries()[index].crc32();
+
+    let mut reader = zip.reader_without_entry(index).await?;
+    let mut writer = BufWriter::new(File::create(dest.as_ref()).await?);
+    let bytes = tokio::io::copy(&mut (&mut reader).compat(), &mut writer).await?;
+    writer.flush().await?;
+
+    if reader.compute_hash() != crc32 {
+        let err = eyre!("CRC32 mismatch of {} in zip archive s3://{}/{}", member, bucket, key);
+        return Err(UtilsError::UnexpectedError(err));
+    }
+    info!(bucket, key, member, file_name = %dest.as_ref().display(), bytes, "extracted zip member");
+
+    Ok(bytes)
+}
+
+async fn open_zip(client: Client, bucket: &str, key: &str) -> Result<S3ZipReader, UtilsError> {
+    let reader = S3SeekableReader::new(client, &S3Path::new(bucket, key)).await?;
+
+    Ok(ZipFileReader::with_tokio(BufReader::new(reader)).await?)
+}